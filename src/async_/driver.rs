@@ -0,0 +1,319 @@
+use std::{
+    future::Future,
+    sync::{Arc, LazyLock},
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    sync::{Clock, SystemClock, TimerHandle, TimerPool},
+    thread::TimerThread,
+};
+
+use super::{Elapsed, Interval, timeout::timeout_by};
+
+/// wakes async timers from a [`TimerThread`], so the timers work under any executor.
+///
+/// [`sleep`](super::sleep) and everything built on it use [`TimerDriver::global`] unless they
+/// are polled by [`block_on`](super::block_on).
+///
+/// a driver with a [`ManualClock`](crate::sync::ManualClock) fires its timers as soon as the
+/// clock is advanced, for deterministic tests.
+pub struct TimerDriver {
+    timer_thread: TimerThread,
+    clock: Arc<dyn Clock>,
+}
+
+impl std::fmt::Debug for TimerDriver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimerDriver")
+            .field("is_manual", &self.clock.is_manual())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for TimerDriver {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimerDriver {
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_builder(std::thread::Builder::new()).expect("failed to create thread")
+    }
+
+    #[inline]
+    pub fn with_builder(builder: std::thread::Builder) -> std::io::Result<Self> {
+        Self::with_builder_and_clock(builder, Arc::new(SystemClock))
+    }
+
+    #[inline]
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self::with_builder_and_clock(std::thread::Builder::new(), clock)
+            .expect("failed to create thread")
+    }
+
+    pub fn with_builder_and_clock(
+        builder: std::thread::Builder,
+        clock: Arc<dyn Clock>,
+    ) -> std::io::Result<Self> {
+        let timer_pool = TimerPool::with_clock(clock.clone());
+        let timer_thread = TimerThread::with_builder_and_pool(builder, timer_pool)?;
+        Ok(Self {
+            timer_thread,
+            clock,
+        })
+    }
+
+    /// the process-wide driver, lazily spawns its thread at the first usage.
+    pub fn global() -> &'static Self {
+        static GLOBAL: LazyLock<TimerDriver> = LazyLock::new(|| {
+            let builder = std::thread::Builder::new().name("sak_rs timer driver".into());
+            TimerDriver::with_builder(builder).expect("failed to create thread")
+        });
+        &GLOBAL
+    }
+
+    #[inline]
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// now by the clock of this driver.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// wakes `waker` once `deadline` is reached, `waker` is dropped if the timer is cancelled.
+    #[inline]
+    pub fn register(&self, deadline: Instant, waker: Waker) -> TimerHandle {
+        self.timer_thread.add_task(deadline, move |_| waker.wake())
+    }
+
+    /// similar to [`sleep`](super::sleep), but always driven by this driver.
+    #[inline]
+    pub async fn sleep(&self, duration: Duration) {
+        self.sleep_until(self.now() + duration).await
+    }
+
+    /// similar to [`sleep_until`](super::sleep_until), but always driven by this driver.
+    pub async fn sleep_until(&self, deadline: Instant) {
+        sleep_until_by(&*self.clock, deadline, |waker| {
+            self.register(deadline, waker.clone())
+        })
+        .await
+    }
+
+    /// similar to [`timeout`](super::timeout), but always driven by this driver.
+    #[inline]
+    pub async fn timeout<F: Future>(&self, duration: Duration, f: F) -> Result<F::Output, Elapsed> {
+        self.timeout_at(self.now() + duration, f).await
+    }
+
+    /// similar to [`timeout_at`](super::timeout_at), but always driven by this driver.
+    #[inline]
+    pub async fn timeout_at<F: Future>(
+        &self,
+        deadline: Instant,
+        f: F,
+    ) -> Result<F::Output, Elapsed> {
+        timeout_by(f, self.sleep_until(deadline)).await
+    }
+
+    /// similar to [`interval`](super::interval), but always driven by this driver.
+    ///
+    /// # Panics
+    ///
+    /// panics if `period` is zero.
+    #[inline]
+    pub fn interval(self: &Arc<Self>, period: Duration) -> Interval {
+        self.interval_at(self.now(), period)
+    }
+
+    /// similar to [`interval_at`](super::interval_at), but always driven by this driver.
+    ///
+    /// # Panics
+    ///
+    /// panics if `period` is zero.
+    #[inline]
+    pub fn interval_at(self: &Arc<Self>, start: Instant, period: Duration) -> Interval {
+        super::interval::interval_by(Some(self.clone()), start, period)
+    }
+}
+
+/// a registered waker, the timer is cancelled when dropped.
+struct Registration {
+    waker: Waker,
+    handle: TimerHandle,
+}
+
+impl Drop for Registration {
+    #[inline]
+    fn drop(&mut self) {
+        self.handle.cancel();
+    }
+}
+
+/// waits until `deadline`, calls `register` whenever the waker needs to be (re)registered.
+///
+/// the timer of the old waker is cancelled, and so is the last one when the future is dropped,
+/// so the waker isn't kept alive until `deadline`.
+pub(crate) fn sleep_until_by(
+    clock: &dyn Clock,
+    deadline: Instant,
+    mut register: impl FnMut(&Waker) -> TimerHandle,
+) -> impl Future<Output = ()> {
+    let mut registered: Option<Registration> = None;
+    std::future::poll_fn(move |cx| {
+        if clock.now() >= deadline {
+            registered = None;
+            return Poll::Ready(());
+        }
+        // the task may be moved to another waker between polls.
+        if !registered
+            .as_ref()
+            .is_some_and(|r| r.waker.will_wake(cx.waker()))
+        {
+            registered = Some(Registration {
+                waker: cx.waker().clone(),
+                handle: register(cx.waker()),
+            });
+        }
+        Poll::Pending
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        sync::{
+            Arc,
+            atomic::{self, AtomicUsize},
+        },
+        task::{Context, Wake},
+        thread::Thread,
+    };
+
+    use super::*;
+
+    struct CountWaker {
+        thread: Thread,
+        count: AtomicUsize,
+    }
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.count.fetch_add(1, atomic::Ordering::Relaxed);
+            self.thread.unpark();
+        }
+    }
+
+    #[test]
+    fn t_foreign_executor() {
+        let count_waker = Arc::new(CountWaker {
+            thread: std::thread::current(),
+            count: AtomicUsize::new(0),
+        });
+        let waker = Waker::from(count_waker.clone());
+        let mut cx = Context::from_waker(&waker);
+        let begin = Instant::now();
+        let mut f = pin!(crate::async_::sleep(Duration::from_millis(200)));
+        while f.as_mut().poll(&mut cx).is_pending() {
+            std::thread::park();
+        }
+        assert!(begin.elapsed() >= Duration::from_millis(200));
+        // woken up only by the timer, no busy spin.
+        assert_eq!(count_waker.count.load(atomic::Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn cancel_on_drop() {
+        let new_waker = || {
+            Arc::new(CountWaker {
+                thread: std::thread::current(),
+                count: AtomicUsize::new(0),
+            })
+        };
+        let (waker_0, waker_1) = (new_waker(), new_waker());
+        let driver = TimerDriver::new();
+        {
+            let mut f = pin!(driver.sleep(Duration::from_secs(3600)));
+            let waker = Waker::from(waker_0.clone());
+            assert!(
+                f.as_mut()
+                    .poll(&mut Context::from_waker(&waker))
+                    .is_pending()
+            );
+            drop(waker);
+            // held by the future and the timer.
+            assert_eq!(Arc::strong_count(&waker_0), 3);
+
+            // moved to another waker, the old timer is cancelled.
+            let waker = Waker::from(waker_1.clone());
+            assert!(
+                f.as_mut()
+                    .poll(&mut Context::from_waker(&waker))
+                    .is_pending()
+            );
+            drop(waker);
+            assert_eq!(Arc::strong_count(&waker_0), 1);
+            assert_eq!(Arc::strong_count(&waker_1), 3);
+        }
+        assert_eq!(Arc::strong_count(&waker_1), 1);
+
+        // the inner future of a timeout finishes first.
+        let waker = Waker::from(waker_0.clone());
+        let f = pin!(driver.timeout(Duration::from_secs(3600), async { 1 }));
+        let r = f.poll(&mut Context::from_waker(&waker));
+        assert!(matches!(r, Poll::Ready(Ok(1))));
+        drop(waker);
+        assert_eq!(Arc::strong_count(&waker_0), 1);
+        assert_eq!(waker_0.count.load(atomic::Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn manual_clock() {
+        use crate::sync::ManualClock;
+
+        let clock = Arc::new(ManualClock::new());
+        let driver = Arc::new(TimerDriver::with_clock(clock.clone()));
+        let start = driver.now();
+        let hour = Duration::from_secs(3600);
+        let mut f = pin!(async {
+            driver.sleep(hour).await;
+            assert_eq!(driver.now(), start + hour);
+            let r = driver.timeout(hour, driver.sleep(hour * 2)).await;
+            assert!(r.is_err());
+            assert_eq!(driver.now(), start + hour * 2);
+
+            let mut interval = driver.interval(hour);
+            interval.set_precise(true);
+            let begin = interval.tick().await;
+            for i in 1..=3 {
+                assert_eq!(interval.tick().await, begin + hour * i);
+            }
+            driver.now() - start
+        });
+        // the clock only moves when advanced, each step is polled right after.
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(f.as_mut().poll(&mut cx).is_pending());
+        // halfway of the sleep, then the sleep ends.
+        clock.advance(hour / 2);
+        assert!(f.as_mut().poll(&mut cx).is_pending());
+        clock.advance(hour / 2);
+        assert!(f.as_mut().poll(&mut cx).is_pending());
+        // the timeout elapses, then 2 ticks of the interval.
+        for _ in 0..3 {
+            clock.advance(hour);
+            assert!(f.as_mut().poll(&mut cx).is_pending());
+        }
+        clock.advance(hour);
+        assert_eq!(f.as_mut().poll(&mut cx), Poll::Ready(hour * 5));
+        assert_eq!(driver.now(), start + hour * 5);
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use super::{TimerDriver, sleep_until};
+
+/// below this, [`Interval`] finishes the tick with [`crate::thread::precise_sleep`] in precise
+/// mode.
+const PRECISE_THRESHOLD: Duration = Duration::from_millis(2);
+
+/// how [`Interval`] behaves when ticks are missed, e.g. the task is not polled in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MissedTickBehavior {
+    /// completes the missed ticks immediately, until it catches up with the schedule.
+    #[default]
+    Burst,
+    /// the next tick happens `period` after the missed one is completed.
+    Delay,
+    /// skips the missed ticks, the next tick is still aligned to the schedule.
+    ///
+    /// see [`crate::math::find_next_tick`].
+    Skip,
+}
+
+/// ticks at a fixed rate, see [`interval`].
+#[derive(Debug)]
+pub struct Interval {
+    next_tick: Instant,
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+    precise: bool,
+    /// `None` for [`sleep_until`].
+    driver: Option<Arc<TimerDriver>>,
+}
+
+impl Interval {
+    /// completes at the next tick, returns the scheduled instant of the tick.
+    pub async fn tick(&mut self) -> Instant {
+        let tick = self.next_tick;
+        // a manual clock is never between two ticks by itself.
+        let is_manual = self.driver.as_ref().is_some_and(|d| d.clock().is_manual());
+        if self.precise && !is_manual {
+            let coarse_tick = tick.checked_sub(PRECISE_THRESHOLD).unwrap_or(tick);
+            self.sleep_until(coarse_tick).await;
+            tick.checked_duration_since(self.now())
+                .map(crate::thread::precise_sleep);
+        } else {
+            self.sleep_until(tick).await;
+        }
+        self.next_tick = self.find_next_tick(tick, self.now());
+        tick
+    }
+
+    /// the next tick happens `period` from now.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.next_tick = self.now() + self.period;
+    }
+
+    #[inline]
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    #[inline]
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed_tick_behavior
+    }
+
+    #[inline]
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+
+    #[inline]
+    pub fn is_precise(&self) -> bool {
+        self.precise
+    }
+
+    /// in precise mode, the end of each tick is waited by [`crate::thread::precise_sleep`],
+    /// which blocks the executor thread for a short time (at most 2ms) but gets sub-millisecond
+    /// accuracy.
+    #[inline]
+    pub fn set_precise(&mut self, precise: bool) {
+        self.precise = precise;
+    }
+}
+
+impl Interval {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.driver.as_ref().map_or_else(Instant::now, |d| d.now())
+    }
+
+    async fn sleep_until(&self, deadline: Instant) {
+        match &self.driver {
+            Some(driver) => driver.sleep_until(deadline).await,
+            None => sleep_until(deadline).await,
+        }
+    }
+
+    fn find_next_tick(&self, tick: Instant, instant_now: Instant) -> Instant {
+        let next_tick = tick + self.period;
+        if instant_now < next_tick {
+            return next_tick;
+        }
+        match self.missed_tick_behavior {
+            MissedTickBehavior::Burst => next_tick,
+            MissedTickBehavior::Delay => instant_now + self.period,
+            MissedTickBehavior::Skip => {
+                crate::math::find_next_tick(tick, instant_now, self.period).unwrap_or(next_tick)
+            }
+        }
+    }
+}
+
+/// creates an [`Interval`] whose first tick completes immediately.
+///
+/// # Panics
+///
+/// panics if `period` is zero.
+#[inline]
+pub fn interval(period: Duration) -> Interval {
+    interval_at(Instant::now(), period)
+}
+
+/// creates an [`Interval`] whose first tick completes at `start`.
+///
+/// # Panics
+///
+/// panics if `period` is zero.
+#[inline]
+pub fn interval_at(start: Instant, period: Duration) -> Interval {
+    interval_by(None, start, period)
+}
+
+pub(crate) fn interval_by(
+    driver: Option<Arc<TimerDriver>>,
+    start: Instant,
+    period: Duration,
+) -> Interval {
+    assert!(!period.is_zero(), "interval period must be non-zero");
+    Interval {
+        next_tick: start,
+        period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+        precise: false,
+        driver,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::async_::FutureWait;
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let period = Duration::from_millis(50);
+        let mut interval = interval(period);
+        interval.set_precise(true);
+        let start = async {
+            let start = interval.tick().await;
+            for i in 1..=5 {
+                let tick = interval.tick().await;
+                assert!(Instant::now() >= tick);
+                assert_eq!(tick, start + period * i);
+            }
+            start
+        }
+        .wait();
+        assert!(start.elapsed() >= period * 5);
+    }
+
+    #[test]
+    fn missed_tick() {
+        let period = Duration::from_millis(50);
+        let stall = period * 7 / 2;
+        let f = async |behavior| {
+            let mut interval = interval(period);
+            interval.set_missed_tick_behavior(behavior);
+            let start = interval.tick().await;
+            std::thread::sleep(stall);
+            let tick = interval.tick().await;
+            let next_tick = interval.tick().await;
+            (tick - start, next_tick - start)
+        };
+        let (tick, next_tick) = f(MissedTickBehavior::Burst).wait();
+        assert_eq!((tick, next_tick), (period, period * 2));
+        let (tick, next_tick) = f(MissedTickBehavior::Skip).wait();
+        assert_eq!((tick, next_tick), (period, period * 4));
+        let (tick, next_tick) = f(MissedTickBehavior::Delay).wait();
+        assert_eq!(tick, period);
+        assert!(next_tick >= stall + period);
+    }
+}
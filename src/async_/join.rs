@@ -0,0 +1,347 @@
+use std::{future::Future, ops::ControlFlow, pin::Pin, task::Poll};
+
+use super::wake::ReadySet;
+
+macro_rules! join_fn {
+    ($(#[$meta:meta])* $name:ident, $len:literal; $($R:ident $f:ident $r:ident $index:literal),+) => {
+        $(#[$meta])*
+        #[allow(clippy::too_many_arguments)]
+        pub async fn $name<$($R),+>($($f: impl Future<Output = $R>),+) -> ($($R,)+) {
+            $(
+                let mut $f = std::pin::pin!($f);
+                let mut $r = None;
+            )+
+            let mut ready_set = ReadySet::new($len);
+            std::future::poll_fn(move |cx| {
+                let _ = ready_set.poll_woken(cx.waker(), |index, cx| {
+                    match index {
+                        $(
+                            $index => {
+                                if $r.is_none()
+                                    && let Poll::Ready(r) = $f.as_mut().poll(cx)
+                                {
+                                    $r = Some(r);
+                                }
+                            }
+                        )+
+                        _ => unreachable!(),
+                    }
+                    ControlFlow::<()>::Continue(())
+                });
+                if $($r.is_some())&&+ {
+                    let r = unsafe { ($($r.take().unwrap_unchecked(),)+) };
+                    Poll::Ready(r)
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await
+        }
+    };
+}
+
+macro_rules! try_join_fn {
+    ($(#[$meta:meta])* $name:ident, $len:literal; $($R:ident $f:ident $r:ident $index:literal),+) => {
+        $(#[$meta])*
+        #[allow(clippy::too_many_arguments)]
+        pub async fn $name<$($R,)+ E>(
+            $($f: impl Future<Output = Result<$R, E>>),+
+        ) -> Result<($($R,)+), E> {
+            $(
+                let mut $f = std::pin::pin!($f);
+                let mut $r = None;
+            )+
+            let mut ready_set = ReadySet::new($len);
+            std::future::poll_fn(move |cx| {
+                let flow = ready_set.poll_woken(cx.waker(), |index, cx| {
+                    match index {
+                        $(
+                            $index => {
+                                if $r.is_none()
+                                    && let Poll::Ready(r) = $f.as_mut().poll(cx)
+                                {
+                                    match r {
+                                        Ok(r) => $r = Some(r),
+                                        Err(err) => return ControlFlow::Break(err),
+                                    }
+                                }
+                            }
+                        )+
+                        _ => unreachable!(),
+                    }
+                    ControlFlow::Continue(())
+                });
+                if let ControlFlow::Break(err) = flow {
+                    return Poll::Ready(Err(err));
+                }
+                if $($r.is_some())&&+ {
+                    let r = unsafe { ($($r.take().unwrap_unchecked(),)+) };
+                    Poll::Ready(Ok(r))
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await
+        }
+    };
+}
+
+join_fn!(
+    /// polls both futures concurrently, only re-polls the ones that have been woken up.
+    join, 2; R1 f1 r1 0, R2 f2 r2 1
+);
+join_fn!(
+    /// see [`join`].
+    join3, 3; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2
+);
+join_fn!(
+    /// see [`join`].
+    join4, 4; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3
+);
+join_fn!(
+    /// see [`join`].
+    join5, 5; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4
+);
+join_fn!(
+    /// see [`join`].
+    join6, 6; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5
+);
+join_fn!(
+    /// see [`join`].
+    join7, 7;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6
+);
+join_fn!(
+    /// see [`join`].
+    join8, 8;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7
+);
+join_fn!(
+    /// see [`join`].
+    join9, 9;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8
+);
+join_fn!(
+    /// see [`join`].
+    join10, 10;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8, R10 f10 r10 9
+);
+join_fn!(
+    /// see [`join`].
+    join11, 11;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8, R10 f10 r10 9, R11 f11 r11 10
+);
+join_fn!(
+    /// see [`join`].
+    join12, 12;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8, R10 f10 r10 9, R11 f11 r11 10, R12 f12 r12 11
+);
+
+try_join_fn!(
+    /// similar to [`join`], but returns the first `Err` immediately.
+    ///
+    /// the remaining futures are dropped without being polled again.
+    try_join, 2; R1 f1 r1 0, R2 f2 r2 1
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join3, 3; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join4, 4; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join5, 5; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join6, 6; R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join7, 7;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join8, 8;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join9, 9;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join10, 10;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8, R10 f10 r10 9
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join11, 11;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8, R10 f10 r10 9, R11 f11 r11 10
+);
+try_join_fn!(
+    /// see [`try_join`].
+    try_join12, 12;
+    R1 f1 r1 0, R2 f2 r2 1, R3 f3 r3 2, R4 f4 r4 3, R5 f5 r5 4, R6 f6 r6 5, R7 f7 r7 6,
+    R8 f8 r8 7, R9 f9 r9 8, R10 f10 r10 9, R11 f11 r11 10, R12 f12 r12 11
+);
+
+/// polls all futures concurrently, returns their outputs in the same order.
+///
+/// only the futures that have been woken up are re-polled.
+pub async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut futures: Pin<Box<[F]>> = Box::into_pin(futures.into_iter().collect());
+    let mut outputs: Vec<Option<F::Output>> = (0..futures.len()).map(|_| None).collect();
+    let mut remaining = futures.len();
+    let mut ready_set = ReadySet::new(futures.len());
+    std::future::poll_fn(move |cx| {
+        let _ = ready_set.poll_woken(cx.waker(), |index, cx| {
+            let output = &mut outputs[index];
+            if output.is_none() {
+                // Safety: elements of a pinned slice are never moved.
+                let f = unsafe { futures.as_mut().map_unchecked_mut(|f| &mut f[index]) };
+                if let Poll::Ready(r) = f.poll(cx) {
+                    *output = Some(r);
+                    remaining -= 1;
+                }
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        if remaining == 0 {
+            let r = outputs
+                .drain(..)
+                .map(|r| unsafe { r.unwrap_unchecked() })
+                .collect();
+            Poll::Ready(r)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// similar to [`join_all`], but returns the first `Err` immediately.
+pub async fn try_join_all<T, E, F: Future<Output = Result<T, E>>>(
+    futures: impl IntoIterator<Item = F>,
+) -> Result<Vec<T>, E> {
+    let mut futures: Pin<Box<[F]>> = Box::into_pin(futures.into_iter().collect());
+    let mut outputs: Vec<Option<T>> = (0..futures.len()).map(|_| None).collect();
+    let mut remaining = futures.len();
+    let mut ready_set = ReadySet::new(futures.len());
+    std::future::poll_fn(move |cx| {
+        let flow = ready_set.poll_woken(cx.waker(), |index, cx| {
+            let output = &mut outputs[index];
+            if output.is_none() {
+                // Safety: elements of a pinned slice are never moved.
+                let f = unsafe { futures.as_mut().map_unchecked_mut(|f| &mut f[index]) };
+                if let Poll::Ready(r) = f.poll(cx) {
+                    match r {
+                        Ok(r) => *output = Some(r),
+                        Err(err) => return ControlFlow::Break(err),
+                    }
+                    remaining -= 1;
+                }
+            }
+            ControlFlow::Continue(())
+        });
+        if let ControlFlow::Break(err) = flow {
+            return Poll::Ready(Err(err));
+        }
+        if remaining == 0 {
+            let r = outputs
+                .drain(..)
+                .map(|r| unsafe { r.unwrap_unchecked() })
+                .collect();
+            Poll::Ready(Ok(r))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{self, AtomicUsize},
+        time::Duration,
+    };
+
+    use crate::async_::{FutureWait, race, sleep, yield_now};
+
+    use super::*;
+
+    #[test]
+    fn t_join_all() {
+        let futures = (0..10_u64).map(|i| async move {
+            sleep(Duration::from_millis(100 - i * 10)).await;
+            i
+        });
+        let r = join_all(futures).wait();
+        assert_eq!(r, (0..10).collect::<Vec<_>>());
+        assert!(
+            join_all(Vec::<std::future::Ready<()>>::new())
+                .wait()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn t_join_tuple() {
+        let r = join4(async { 1 }, async { "2" }, yield_now(), async {
+            yield_now().await;
+            4.0
+        })
+        .wait();
+        assert_eq!(r, (1, "2", (), 4.0));
+    }
+
+    #[test]
+    fn t_try_join() {
+        let r = try_join3(
+            async { Ok(1) },
+            async { Err::<(), _>("error") },
+            std::future::pending::<Result<(), _>>(),
+        )
+        .wait();
+        assert_eq!(r, Err("error"));
+
+        let r = try_join_all((0..5).map(|i| async move {
+            yield_now().await;
+            Result::<_, ()>::Ok(i)
+        }))
+        .wait();
+        assert_eq!(r, Ok(vec![0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn t_poll_woken_only() {
+        let polls = AtomicUsize::new(0);
+        let idle = std::future::poll_fn(|_| {
+            polls.fetch_add(1, atomic::Ordering::Relaxed);
+            Poll::<()>::Pending
+        });
+        let busy = async {
+            for _ in 0..100 {
+                yield_now().await;
+            }
+        };
+        let r = race(idle, busy).wait();
+        assert_eq!(r, (1, ()));
+        assert_eq!(polls.load(atomic::Ordering::Relaxed), 1);
+    }
+}
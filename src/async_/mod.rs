@@ -1,3 +1,17 @@
+pub mod driver;
+pub mod interval;
+pub mod join;
+pub mod select;
+pub mod timeout;
+
+mod wake;
+
+pub use driver::TimerDriver;
+pub use interval::{Interval, MissedTickBehavior, interval, interval_at};
+pub use join::*;
+pub use select::*;
+pub use timeout::{Elapsed, timeout, timeout_at};
+
 use std::{
     future::Future,
     sync::{
@@ -9,7 +23,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::thread::TimerThread;
+use crate::{sync::SystemClock, thread::TimerThread};
 
 pub trait FutureWait: Future {
     fn wait(self) -> Self::Output;
@@ -22,6 +36,34 @@ impl<T: Future> FutureWait for T {
     }
 }
 
+pub trait FutureTimeout: Future + Sized {
+    /// see [`timeout`].
+    fn timeout(self, duration: Duration) -> impl Future<Output = Result<Self::Output, Elapsed>>;
+
+    /// see [`timeout_at`].
+    fn timeout_at(self, deadline: Instant) -> impl Future<Output = Result<Self::Output, Elapsed>>;
+
+    /// [`wait`](FutureWait::wait) at most `duration`.
+    fn wait_timeout(self, duration: Duration) -> Result<Self::Output, Elapsed>;
+}
+
+impl<T: Future> FutureTimeout for T {
+    #[inline]
+    fn timeout(self, duration: Duration) -> impl Future<Output = Result<Self::Output, Elapsed>> {
+        timeout(duration, self)
+    }
+
+    #[inline]
+    fn timeout_at(self, deadline: Instant) -> impl Future<Output = Result<Self::Output, Elapsed>> {
+        timeout_at(deadline, self)
+    }
+
+    #[inline]
+    fn wait_timeout(self, duration: Duration) -> Result<Self::Output, Elapsed> {
+        block_on(timeout(duration, self))
+    }
+}
+
 struct ThreadWaker {
     thread: Thread,
     unparked: AtomicBool,
@@ -108,62 +150,25 @@ pub fn block_on_spin<const YIELD: bool, R>(f: impl Future<Output = R>) -> R {
     }
 }
 
-pub async fn join<R1, R2>(f1: impl Future<Output = R1>, f2: impl Future<Output = R2>) -> (R1, R2) {
-    let mut f1 = std::pin::pin!(f1);
-    let mut f2 = std::pin::pin!(f2);
-    let mut r1 = None;
-    let mut r2 = None;
-    std::future::poll_fn(move |cx| {
-        if r1.is_none()
-            && let Poll::Ready(r) = f1.as_mut().poll(cx)
-        {
-            r1 = Some(r);
-        }
-        if r2.is_none()
-            && let Poll::Ready(r) = f2.as_mut().poll(cx)
-        {
-            r2 = Some(r);
-        }
-        if r1.is_some() && r2.is_some() {
-            let r = unsafe { (r1.take().unwrap_unchecked(), r2.take().unwrap_unchecked()) };
-            Poll::Ready(r)
-        } else {
-            Poll::Pending
-        }
-    })
-    .await
-}
-
-/// only for [`crate::async_::block_on`].
+/// waits for `duration` without blocking the thread.
 ///
-/// otherwise fallback to busy spin.
+/// under [`block_on`] the timer is registered to the thread of `block_on`, otherwise to
+/// [`TimerDriver::global`].
+#[inline]
 pub async fn sleep(duration: Duration) {
-    let deadline = Instant::now() + duration;
-    let mut once = Some(());
-    std::future::poll_fn(|cx| {
-        if let Some(thread_waker) = ThreadWaker::ref_from_waker(cx.waker()) {
-            once.take().map(|_| {
-                let waker = cx.waker().clone();
-                thread_waker
-                    .timer_thread
-                    .add_task(deadline, move |_| waker.wake_by_ref())
-            });
-
-            let time_out = Instant::now() >= deadline;
-            if time_out {
-                Poll::Ready(())
-            } else {
-                Poll::Pending
-            }
+    sleep_until(Instant::now() + duration).await
+}
+
+/// see [`sleep`].
+pub async fn sleep_until(deadline: Instant) {
+    driver::sleep_until_by(&SystemClock, deadline, |waker| {
+        if let Some(thread_waker) = ThreadWaker::ref_from_waker(waker) {
+            let waker = waker.clone();
+            thread_waker
+                .timer_thread
+                .add_task(deadline, move |_| waker.wake())
         } else {
-            // fallback to busy spin
-            let time_out = Instant::now() >= deadline;
-            if time_out {
-                Poll::Ready(())
-            } else {
-                cx.waker().wake_by_ref();
-                Poll::Pending
-            }
+            TimerDriver::global().register(deadline, waker.clone())
         }
     })
     .await
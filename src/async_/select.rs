@@ -0,0 +1,141 @@
+use std::{future::Future, ops::ControlFlow, pin::Pin, task::Poll};
+
+use super::wake::ReadySet;
+
+macro_rules! race_fn {
+    ($(#[$meta:meta])* $name:ident, $len:literal; $($f:ident $index:literal),+) => {
+        $(#[$meta])*
+        #[allow(clippy::too_many_arguments)]
+        pub async fn $name<R>($($f: impl Future<Output = R>),+) -> (usize, R) {
+            $(let mut $f = std::pin::pin!($f);)+
+            let mut ready_set = ReadySet::new($len);
+            std::future::poll_fn(move |cx| {
+                let flow = ready_set.poll_woken(cx.waker(), |index, cx| {
+                    let r = match index {
+                        $($index => $f.as_mut().poll(cx),)+
+                        _ => unreachable!(),
+                    };
+                    match r {
+                        Poll::Ready(r) => ControlFlow::Break((index, r)),
+                        Poll::Pending => ControlFlow::Continue(()),
+                    }
+                });
+                match flow {
+                    ControlFlow::Break(r) => Poll::Ready(r),
+                    ControlFlow::Continue(()) => Poll::Pending,
+                }
+            })
+            .await
+        }
+    };
+}
+
+race_fn!(
+    /// polls both futures concurrently, returns the index and the output of the first ready one.
+    ///
+    /// the other future is dropped.
+    race, 2; f1 0, f2 1
+);
+race_fn!(
+    /// see [`race`].
+    race3, 3; f1 0, f2 1, f3 2
+);
+race_fn!(
+    /// see [`race`].
+    race4, 4; f1 0, f2 1, f3 2, f4 3
+);
+race_fn!(
+    /// see [`race`].
+    race5, 5; f1 0, f2 1, f3 2, f4 3, f5 4
+);
+race_fn!(
+    /// see [`race`].
+    race6, 6; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5
+);
+race_fn!(
+    /// see [`race`].
+    race7, 7; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5, f7 6
+);
+race_fn!(
+    /// see [`race`].
+    race8, 8; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5, f7 6, f8 7
+);
+race_fn!(
+    /// see [`race`].
+    race9, 9; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5, f7 6, f8 7, f9 8
+);
+race_fn!(
+    /// see [`race`].
+    race10, 10; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5, f7 6, f8 7, f9 8, f10 9
+);
+race_fn!(
+    /// see [`race`].
+    race11, 11; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5, f7 6, f8 7, f9 8, f10 9, f11 10
+);
+race_fn!(
+    /// see [`race`].
+    race12, 12; f1 0, f2 1, f3 2, f4 3, f5 4, f6 5, f7 6, f8 7, f9 8, f10 9, f11 10, f12 11
+);
+
+/// polls all futures concurrently, returns the index and the output of the first ready one.
+///
+/// only the futures that have been woken up are re-polled, the others are dropped at the end.
+///
+/// # Panics
+///
+/// panics if `futures` is empty.
+pub async fn select<F: Future>(futures: impl IntoIterator<Item = F>) -> (usize, F::Output) {
+    let mut futures: Pin<Box<[F]>> = Box::into_pin(futures.into_iter().collect());
+    assert!(!futures.is_empty(), "select on empty futures");
+    let mut ready_set = ReadySet::new(futures.len());
+    std::future::poll_fn(move |cx| {
+        let flow = ready_set.poll_woken(cx.waker(), |index, cx| {
+            // Safety: elements of a pinned slice are never moved.
+            let f = unsafe { futures.as_mut().map_unchecked_mut(|f| &mut f[index]) };
+            match f.poll(cx) {
+                Poll::Ready(r) => ControlFlow::Break((index, r)),
+                Poll::Pending => ControlFlow::Continue(()),
+            }
+        });
+        match flow {
+            ControlFlow::Break(r) => Poll::Ready(r),
+            ControlFlow::Continue(()) => Poll::Pending,
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::async_::{FutureWait, sleep, yield_now};
+
+    use super::*;
+
+    #[test]
+    fn t_select() {
+        let futures = [300, 100, 200].map(|ms| async move {
+            sleep(Duration::from_millis(ms)).await;
+            ms
+        });
+        assert_eq!(select(futures).wait(), (1, 100));
+    }
+
+    #[test]
+    fn t_race() {
+        let r = race3(
+            std::future::pending(),
+            async {
+                yield_now().await;
+                "yield"
+            },
+            async {
+                sleep(Duration::from_millis(100)).await;
+                "sleep"
+            },
+        )
+        .wait();
+        assert_eq!(r, (1, "yield"));
+    }
+}
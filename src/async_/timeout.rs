@@ -0,0 +1,64 @@
+use std::{
+    future::Future,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use super::sleep_until;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("deadline has elapsed")]
+pub struct Elapsed;
+
+/// requires `f` to complete within `duration`.
+///
+/// see [`timeout_at`].
+#[inline]
+pub async fn timeout<F: Future>(duration: Duration, f: F) -> Result<F::Output, Elapsed> {
+    timeout_at(Instant::now() + duration, f).await
+}
+
+/// requires `f` to complete before `deadline`, otherwise `f` is dropped and returns
+/// [`Elapsed`].
+///
+/// the deadline is driven by [`sleep_until`], so it has the same behavior under different
+/// executors.
+pub async fn timeout_at<F: Future>(deadline: Instant, f: F) -> Result<F::Output, Elapsed> {
+    timeout_by(f, sleep_until(deadline)).await
+}
+
+/// `f` is dropped once `sleep` completes.
+pub(crate) async fn timeout_by<F: Future>(
+    f: F,
+    sleep: impl Future<Output = ()>,
+) -> Result<F::Output, Elapsed> {
+    let mut f = std::pin::pin!(f);
+    let mut sleep = std::pin::pin!(sleep);
+    std::future::poll_fn(move |cx| {
+        if let Poll::Ready(r) = f.as_mut().poll(cx) {
+            return Poll::Ready(Ok(r));
+        }
+        sleep.as_mut().poll(cx).map(|_| Err(Elapsed))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::async_::{FutureTimeout, FutureWait, sleep};
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let r = sleep(Duration::from_millis(100))
+            .timeout(Duration::from_secs(1))
+            .wait();
+        assert_eq!(r, Ok(()));
+
+        let begin = Instant::now();
+        let r = std::future::pending::<()>().wait_timeout(Duration::from_millis(200));
+        assert_eq!(r, Err(Elapsed));
+        assert!(begin.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    ops::ControlFlow,
+    sync::{
+        Arc,
+        atomic::{self, AtomicBool},
+    },
+    task::{Context, Wake, Waker},
+};
+
+use parking_lot::Mutex;
+
+struct Shared {
+    parent: Mutex<Option<Waker>>,
+    ready: Mutex<Vec<usize>>,
+    queued: Box<[AtomicBool]>,
+}
+
+struct ChildWaker {
+    shared: Arc<Shared>,
+    index: usize,
+}
+
+impl Wake for ChildWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let shared = &*self.shared;
+        let queued = unsafe { shared.queued.get_unchecked(self.index) };
+        if queued.swap(true, atomic::Ordering::AcqRel) {
+            // already in `ready`, the parent has been woken up.
+            return;
+        }
+        shared.ready.lock().push(self.index);
+        shared.parent.lock().as_ref().map(Waker::wake_by_ref);
+    }
+}
+
+/// tracks which children of a combinator have been woken up.
+///
+/// each child gets its own [`Waker`], waking a child records its index and wakes the parent.
+pub(crate) struct ReadySet {
+    shared: Arc<Shared>,
+    wakers: Box<[Waker]>,
+    buf: Vec<usize>,
+}
+
+impl ReadySet {
+    /// all children are ready at the beginning.
+    pub(crate) fn new(len: usize) -> Self {
+        let shared = Arc::new(Shared {
+            parent: Mutex::new(None),
+            ready: Mutex::new((0..len).collect()),
+            queued: (0..len).map(|_| AtomicBool::new(true)).collect(),
+        });
+        let wakers = (0..len)
+            .map(|index| {
+                Waker::from(Arc::new(ChildWaker {
+                    shared: shared.clone(),
+                    index,
+                }))
+            })
+            .collect();
+        Self {
+            shared,
+            wakers,
+            buf: Vec::with_capacity(len),
+        }
+    }
+
+    /// registers `waker` as the parent waker, then calls `f` with the index and the [`Context`]
+    /// of each child woken up since the last call.
+    ///
+    /// if `f` breaks, the remaining children stay ready for the next call.
+    pub(crate) fn poll_woken<B>(
+        &mut self,
+        waker: &Waker,
+        mut f: impl FnMut(usize, &mut Context<'_>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        {
+            let mut parent = self.shared.parent.lock();
+            match &mut *parent {
+                Some(parent) => parent.clone_from(waker),
+                None => *parent = Some(waker.clone()),
+            }
+        }
+        core::mem::swap(&mut self.buf, &mut *self.shared.ready.lock());
+        for pos in 0..self.buf.len() {
+            let index = unsafe { *self.buf.get_unchecked(pos) };
+            unsafe { self.shared.queued.get_unchecked(index) }
+                .store(false, atomic::Ordering::Release);
+            let mut cx = Context::from_waker(unsafe { self.wakers.get_unchecked(index) });
+            if let ControlFlow::Break(b) = f(index, &mut cx) {
+                self.shared
+                    .ready
+                    .lock()
+                    .extend_from_slice(&self.buf[pos + 1..]);
+                self.buf.clear();
+                return ControlFlow::Break(b);
+            }
+        }
+        self.buf.clear();
+        ControlFlow::Continue(())
+    }
+}
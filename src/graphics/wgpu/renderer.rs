@@ -4,7 +4,10 @@ use crossbeam_queue::SegQueue;
 use parking_lot::Mutex;
 use wgpu::{CompositeAlphaMode, CurrentSurfaceTexture};
 
-use crate::{sync::spsc::OnceReceiver, thread::WorkerThread};
+use crate::{
+    sync::spsc::OnceReceiver,
+    thread::{PanicPayload, WorkerThread},
+};
 
 use super::Context;
 
@@ -119,7 +122,7 @@ pub struct Renderer {
 
     shared: Arc<(Shared, Mutex<SharedMut>)>,
     render_worker: WorkerThread,
-    render_receiver: Option<OnceReceiver<()>>,
+    render_receiver: Option<OnceReceiver<Result<(), PanicPayload>>>,
 }
 
 impl Renderer {
@@ -215,7 +218,7 @@ impl Renderer {
         });
         self.render_receiver
             .replace(new_render_receiver)
-            .map(|r| r.recv());
+            .map(|r| r.recv().unwrap_or_else(|p| std::panic::resume_unwind(p)));
     }
 
     /// never blocks, returns true if render is successful.
@@ -225,11 +228,14 @@ impl Renderer {
         &mut self,
         add_commands: impl FnOnce(&mut wgpu::RenderPass) + Send + 'static,
     ) -> bool {
-        if let Some(r) = self.render_receiver.take()
-            && let Err(r) = r.try_recv()
-        {
-            self.render_receiver = Some(r);
-            return false;
+        if let Some(r) = self.render_receiver.take() {
+            match r.try_recv() {
+                Ok(r) => r.unwrap_or_else(|p| std::panic::resume_unwind(p)),
+                Err(r) => {
+                    self.render_receiver = Some(r);
+                    return false;
+                }
+            }
         }
         // self.render_receiver is None here, so self.render never blocks
         self.render(add_commands);
@@ -57,7 +57,7 @@ mod tests {
             if win_msg.msg.message != WM_INPUT {
                 return false;
             }
-            sender.send(win_msg.instant);
+            let _ = sender.send(win_msg.instant);
             true
         };
         let raw_input_hook = |&hwnd: &HWND| {
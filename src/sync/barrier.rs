@@ -0,0 +1,174 @@
+use std::task::Poll;
+
+use parking_lot::{Condvar, Mutex};
+
+use super::waiters::WakerSet;
+
+struct BarrierState {
+    /// number of waiters arrived in this generation.
+    count: usize,
+    /// bumped when all waiters arrived.
+    generation: u64,
+}
+
+/// reusable barrier for both threads and async tasks, like [`std::sync::Barrier`].
+pub struct Barrier {
+    state: Mutex<BarrierState>,
+    num_waiters: usize,
+    condvar: Condvar,
+    wakers: WakerSet,
+}
+
+/// see [`Barrier::wait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BarrierWaitResult {
+    is_leader: bool,
+}
+
+impl BarrierWaitResult {
+    /// `true` for the last waiter arrived in a generation.
+    #[inline]
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+}
+
+impl Barrier {
+    /// `num_waiters` of `0` behaves like `1`.
+    #[inline]
+    pub const fn new(num_waiters: usize) -> Self {
+        Self {
+            state: Mutex::new(BarrierState {
+                count: 0,
+                generation: 0,
+            }),
+            num_waiters,
+            condvar: Condvar::new(),
+            wakers: WakerSet::new(),
+        }
+    }
+
+    /// blocking until `num_waiters` waiters have arrived.
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut state = self.state.lock();
+        let generation = state.generation;
+        if self.arrive(&mut state) {
+            drop(state);
+            self.wakers.wake_all();
+            return BarrierWaitResult { is_leader: true };
+        }
+        self.condvar
+            .wait_while(&mut state, |state| state.generation == generation);
+        BarrierWaitResult { is_leader: false }
+    }
+
+    /// waits without blocking the thread.
+    ///
+    /// the waiter arrives at the first poll, and is still counted if the future is dropped
+    /// before the others arrive.
+    pub async fn wait_async(&self) -> BarrierWaitResult {
+        let mut generation = None;
+        std::future::poll_fn(|cx| {
+            let generation = match generation {
+                Some(generation) => generation,
+                None => {
+                    let mut state = self.state.lock();
+                    if self.arrive(&mut state) {
+                        drop(state);
+                        self.wakers.wake_all();
+                        return Poll::Ready(BarrierWaitResult { is_leader: true });
+                    }
+                    *generation.insert(state.generation)
+                }
+            };
+            self.wakers.register(cx.waker());
+            if self.state.lock().generation == generation {
+                Poll::Pending
+            } else {
+                Poll::Ready(BarrierWaitResult { is_leader: false })
+            }
+        })
+        .await
+    }
+
+    #[inline]
+    pub fn num_waiters(&self) -> usize {
+        self.num_waiters
+    }
+}
+
+impl Barrier {
+    /// returns `true` if this is the last waiter, then starts the next generation.
+    fn arrive(&self, state: &mut BarrierState) -> bool {
+        state.count += 1;
+        if state.count < self.num_waiters {
+            return false;
+        }
+        state.count = 0;
+        state.generation += 1;
+        self.condvar.notify_all();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let barrier = Barrier::new(4);
+        let arrived = AtomicUsize::new(0);
+        let leaders = AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    // reused for several rounds.
+                    for round in 1..=3 {
+                        arrived.fetch_add(1, Ordering::SeqCst);
+                        let r = barrier.wait();
+                        assert!(arrived.load(Ordering::SeqCst) >= round * 4);
+                        r.is_leader()
+                            .then(|| leaders.fetch_add(1, Ordering::SeqCst));
+                        barrier.wait();
+                    }
+                });
+            }
+        });
+        assert_eq!(leaders.into_inner(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "thread_async")]
+    fn async_() {
+        use std::sync::Arc;
+
+        let barrier = Arc::new(Barrier::new(5));
+        let leaders = Arc::new(AtomicUsize::new(0));
+        let pool = crate::thread::AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        // more tasks than workers, and mixed with a thread.
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (barrier, leaders) = (barrier.clone(), leaders.clone());
+                pool.spawn(async move {
+                    for _ in 0..3 {
+                        let r = barrier.wait_async().await;
+                        r.is_leader()
+                            .then(|| leaders.fetch_add(1, Ordering::SeqCst));
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..3 {
+            let r = barrier.wait();
+            r.is_leader()
+                .then(|| leaders.fetch_add(1, Ordering::SeqCst));
+        }
+        handles
+            .into_iter()
+            .for_each(|h| h.join().expect("task panic"));
+        assert_eq!(leaders.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{self, AtomicUsize},
+    },
+    task::Poll,
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use super::waiters::WakerSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvError {
+    #[error("Broadcast channel is disconnected")]
+    Disconnected,
+    /// the receiver is too slow, the oldest `n` values are overwritten and skipped.
+    #[error("Broadcast receiver lagged behind by {0} values")]
+    Lagged(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TryRecvError {
+    #[error("Broadcast channel is disconnected")]
+    Disconnected,
+    #[error("Broadcast channel is empty")]
+    Empty,
+    /// see [`RecvError::Lagged`].
+    #[error("Broadcast receiver lagged behind by {0} values")]
+    Lagged(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum SendError<T> {
+    #[error("Broadcast channel is disconnected")]
+    Disconnected(T),
+}
+
+struct RingBuffer<T> {
+    slots: Box<[Option<T>]>,
+    /// position of the next value, the value at `pos` is in `slots[pos % capacity]`.
+    tail: u64,
+}
+
+impl<T> RingBuffer<T> {
+    #[inline]
+    fn capacity(&self) -> u64 {
+        self.slots.len() as u64
+    }
+
+    /// position of the oldest value in the buffer.
+    #[inline]
+    fn head(&self) -> u64 {
+        self.tail.saturating_sub(self.capacity())
+    }
+
+    /// reads the value at `next` and advances it.
+    ///
+    /// `is_disconnected` must be loaded while `self` is locked, the senders are dropped while
+    /// locked, so no value is missed.
+    fn read(&self, next: &mut u64, is_disconnected: bool) -> Result<T, TryRecvError>
+    where
+        T: Clone,
+    {
+        let head = self.head();
+        if *next < head {
+            let lagged = head - *next;
+            *next = head;
+            return Err(TryRecvError::Lagged(lagged));
+        }
+        if *next == self.tail {
+            return Err(if is_disconnected {
+                TryRecvError::Disconnected
+            } else {
+                TryRecvError::Empty
+            });
+        }
+        let index = (*next % self.capacity()) as usize;
+        *next += 1;
+        // Safety: positions in `head..tail` are always filled.
+        Ok(unsafe { self.slots[index].clone().unwrap_unchecked() })
+    }
+}
+
+struct Shared<T> {
+    buf: Mutex<RingBuffer<T>>,
+    condvar: Condvar,
+    wakers: WakerSet,
+    num_senders: AtomicUsize,
+    num_receivers: AtomicUsize,
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn is_disconnected(&self) -> bool {
+        self.num_senders.load(atomic::Ordering::Acquire) == 0
+    }
+}
+
+/// every receiver receives every value sent after it is created, in order.
+pub struct BroadcastReceiver<T> {
+    shared: Arc<Shared<T>>,
+    /// position of the next value to receive.
+    next: u64,
+}
+
+impl<T: Clone> BroadcastReceiver<T> {
+    /// values sent before the last sender is dropped are still received.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let buf = self.shared.buf.lock();
+        buf.read(&mut self.next, self.shared.is_disconnected())
+    }
+
+    /// blocking until a value is received.
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        let mut buf = self.shared.buf.lock();
+        loop {
+            match buf.read(&mut self.next, self.shared.is_disconnected()) {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Lagged(n)) => return Err(RecvError::Lagged(n)),
+                Err(TryRecvError::Disconnected) => return Err(RecvError::Disconnected),
+                Err(TryRecvError::Empty) => self.shared.condvar.wait(&mut buf),
+            }
+        }
+    }
+
+    /// receives a value without blocking the thread.
+    pub async fn recv_async(&mut self) -> Result<T, RecvError> {
+        std::future::poll_fn(|cx| {
+            if let Poll::Ready(r) = self.poll_recv() {
+                return Poll::Ready(r);
+            }
+            self.shared.wakers.register(cx.waker());
+            self.poll_recv()
+        })
+        .await
+    }
+}
+
+impl<T> BroadcastReceiver<T> {
+    /// number of values not received yet, including the overwritten ones.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.shared.buf.lock().tail - self.next
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `true` if all senders are dropped.
+    #[inline]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.is_disconnected()
+    }
+}
+
+impl<T: Clone> BroadcastReceiver<T> {
+    #[inline]
+    fn poll_recv(&mut self) -> Poll<Result<T, RecvError>> {
+        match self.try_recv() {
+            Ok(value) => Poll::Ready(Ok(value)),
+            Err(TryRecvError::Lagged(n)) => Poll::Ready(Err(RecvError::Lagged(n))),
+            Err(TryRecvError::Disconnected) => Poll::Ready(Err(RecvError::Disconnected)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Clone for BroadcastReceiver<T> {
+    /// the clone starts at the same position.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared
+            .num_receivers
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        Self {
+            shared: self.shared.clone(),
+            next: self.next,
+        }
+    }
+}
+
+impl<T> Drop for BroadcastReceiver<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.shared
+            .num_receivers
+            .fetch_sub(1, atomic::Ordering::Release);
+    }
+}
+
+pub struct BroadcastSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> BroadcastSender<T> {
+    /// returns the number of receivers the value is sent to.
+    ///
+    /// if the buffer is full, the oldest value is overwritten, and the receivers which haven't
+    /// received it get [`RecvError::Lagged`].
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let num_receivers = self.receiver_count();
+        if num_receivers == 0 {
+            return Err(SendError::Disconnected(value));
+        }
+        {
+            let mut buf = self.shared.buf.lock();
+            let index = (buf.tail % buf.capacity()) as usize;
+            buf.slots[index] = Some(value);
+            buf.tail += 1;
+            self.shared.condvar.notify_all();
+        }
+        self.shared.wakers.wake_all();
+        Ok(num_receivers)
+    }
+
+    /// a new receiver, which receives the values sent after this call.
+    pub fn subscribe(&self) -> BroadcastReceiver<T> {
+        self.shared
+            .num_receivers
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        let next = self.shared.buf.lock().tail;
+        BroadcastReceiver {
+            shared: self.shared.clone(),
+            next,
+        }
+    }
+
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.num_receivers.load(atomic::Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.buf.lock().slots.len()
+    }
+}
+
+impl<T> Clone for BroadcastSender<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared
+            .num_senders
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for BroadcastSender<T> {
+    fn drop(&mut self) {
+        {
+            let _buf = self.shared.buf.lock();
+            if self
+                .shared
+                .num_senders
+                .fetch_sub(1, atomic::Ordering::Release)
+                != 1
+            {
+                return;
+            }
+            self.shared.condvar.notify_all();
+        }
+        self.shared.wakers.wake_all();
+    }
+}
+
+/// broadcast channel, each value is cloned to every receiver.
+///
+/// the values are kept in a ring buffer of `capacity`, a receiver more than `capacity` values
+/// behind skips the overwritten ones.
+///
+/// # Panics
+///
+/// panics if `capacity` is zero.
+pub fn broadcast<T: Clone + Send>(capacity: usize) -> (BroadcastSender<T>, BroadcastReceiver<T>) {
+    assert!(capacity != 0, "`capacity` must be non-zero");
+    let shared = Arc::new(Shared {
+        buf: Mutex::new(RingBuffer {
+            slots: (0..capacity).map(|_| None).collect(),
+            tail: 0,
+        }),
+        condvar: Condvar::new(),
+        wakers: WakerSet::new(),
+        num_senders: AtomicUsize::new(1),
+        num_receivers: AtomicUsize::new(1),
+    });
+    let sender = BroadcastSender {
+        shared: shared.clone(),
+    };
+    let receiver = BroadcastReceiver { shared, next: 0 };
+    (sender, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let (sender, mut receiver) = broadcast::<usize>(16);
+        let receivers: Vec<_> = (0..3).map(|_| sender.subscribe()).collect();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        std::thread::scope(|s| {
+            let handles: Vec<_> = receivers
+                .into_iter()
+                .map(|mut receiver| {
+                    s.spawn(move || {
+                        let mut r = Vec::new();
+                        loop {
+                            match receiver.recv() {
+                                Ok(value) => r.push(value),
+                                Err(RecvError::Lagged(n)) => panic!("lagged {n}"),
+                                Err(RecvError::Disconnected) => return r,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            let sender = sender;
+            for i in 0..8 {
+                assert_eq!(sender.send(i).ok(), Some(4));
+            }
+            drop(sender);
+            for h in handles {
+                assert_eq!(
+                    h.join().expect("receiver panic"),
+                    (0..8).collect::<Vec<_>>()
+                );
+            }
+        });
+        assert_eq!(receiver.len(), 8);
+        assert_eq!(receiver.recv(), Ok(0));
+        let rest: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert_eq!(rest, (1..8).collect::<Vec<_>>());
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn lagged() {
+        let (sender, mut receiver) = broadcast::<usize>(4);
+        (0..10).for_each(|i| assert!(sender.send(i).is_ok()));
+        let mut clone = receiver.clone();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Lagged(6)));
+        assert_eq!(receiver.try_recv(), Ok(6));
+        assert_eq!(clone.recv(), Err(RecvError::Lagged(6)));
+        assert_eq!(clone.recv(), Ok(6));
+
+        // subscribers only see new values.
+        let mut late = sender.subscribe();
+        assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+        drop((receiver, clone, late));
+        assert_eq!(sender.send(0), Err(SendError::Disconnected(0)));
+    }
+
+    #[test]
+    #[cfg(feature = "thread_async")]
+    fn recv_async() {
+        use std::time::Duration;
+
+        // never lags.
+        let (sender, receiver) = broadcast::<usize>(16);
+        let thread_pool = crate::thread::AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let mut receiver = receiver.clone();
+                thread_pool.spawn(async move {
+                    let mut sum = 0;
+                    while let Ok(value) = receiver.recv_async().await {
+                        sum += value;
+                    }
+                    sum
+                })
+            })
+            .collect();
+        drop(receiver);
+        for i in 1..=10 {
+            assert_eq!(sender.send(i).ok(), Some(4));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        drop(sender);
+        for h in handles {
+            assert_eq!(h.join().ok(), Some(55));
+        }
+    }
+}
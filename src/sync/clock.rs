@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::thread::precise_sleep;
+
+/// called after a [`Clock`] is advanced manually, removed once it returns `false`.
+pub type AdvanceListener = dyn Fn() -> bool + Send + Sync + 'static;
+
+/// source of time for [`TimerPool`](super::TimerPool) and the timers built on it.
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> Instant;
+
+    /// blocks the current thread until `deadline` by this clock.
+    fn sleep_until(&self, deadline: Instant);
+
+    /// a manual clock doesn't advance by itself, the time only changes before the calls to the
+    /// listeners added by [`on_advance`](Self::on_advance).
+    #[inline]
+    fn is_manual(&self) -> bool {
+        false
+    }
+
+    /// does nothing if the clock is not manual.
+    #[inline]
+    fn on_advance(&self, listener: Box<AdvanceListener>) {
+        let _ = listener;
+    }
+}
+
+/// the real time, by [`Instant::now`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        deadline
+            .checked_duration_since(Instant::now())
+            .map(precise_sleep);
+    }
+}
+
+/// a clock only advanced by hand, for deterministic tests.
+///
+/// [`sleep_until`](Clock::sleep_until) doesn't block, it advances the clock to the deadline
+/// instead.
+pub struct ManualClock {
+    now: Mutex<Instant>,
+    listeners: Mutex<Vec<Box<AdvanceListener>>>,
+}
+
+impl Default for ManualClock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    /// starts at [`Instant::now`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_start(Instant::now())
+    }
+
+    #[inline]
+    pub fn with_start(start: Instant) -> Self {
+        Self {
+            now: Mutex::new(start),
+            listeners: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[inline]
+    pub fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+
+    #[inline]
+    pub fn advance(&self, duration: Duration) {
+        let instant = self.now() + duration;
+        self.advance_to(instant);
+    }
+
+    /// does nothing if `instant` is earlier than now, the clock never goes back.
+    pub fn advance_to(&self, instant: Instant) {
+        {
+            let mut now = self.now.lock();
+            if instant <= *now {
+                return;
+            }
+            *now = instant;
+        }
+        self.listeners.lock().retain(|listener| listener());
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        ManualClock::now(self)
+    }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Instant) {
+        self.advance_to(deadline);
+    }
+
+    #[inline]
+    fn is_manual(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn on_advance(&self, listener: Box<AdvanceListener>) {
+        self.listeners.lock().push(listener);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let start = Instant::now();
+        let clock = ManualClock::with_start(start);
+        let count = std::sync::Arc::new(AtomicUsize::new(0));
+        let c = count.clone();
+        clock.on_advance(Box::new(move || c.fetch_add(1, Ordering::Relaxed) < 1));
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(clock.now(), start + Duration::from_secs(3600));
+        clock.sleep_until(start + Duration::from_secs(7200));
+        assert_eq!(clock.now(), start + Duration::from_secs(7200));
+        // never goes back, and the listener is not called.
+        clock.advance_to(start);
+        assert_eq!(clock.now(), start + Duration::from_secs(7200));
+        // removed after returning `false`.
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
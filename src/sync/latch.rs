@@ -0,0 +1,134 @@
+use std::{
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use super::waiters::WakerSet;
+
+/// releases the waiters once counted down to zero, can't be reset.
+pub struct CountDownLatch {
+    count: Mutex<usize>,
+    condvar: Condvar,
+    wakers: WakerSet,
+}
+
+impl CountDownLatch {
+    #[inline]
+    pub const fn new(count: usize) -> Self {
+        Self {
+            count: Mutex::new(count),
+            condvar: Condvar::new(),
+            wakers: WakerSet::new(),
+        }
+    }
+
+    /// does nothing if the count is already zero.
+    pub fn count_down(&self) {
+        {
+            let mut count = self.count.lock();
+            if *count == 0 {
+                return;
+            }
+            *count -= 1;
+            if *count != 0 {
+                return;
+            }
+            self.condvar.notify_all();
+        }
+        self.wakers.wake_all();
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        *self.count.lock()
+    }
+
+    #[inline]
+    pub fn is_released(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// blocking until the count is zero.
+    pub fn wait(&self) {
+        let mut count = self.count.lock();
+        self.condvar.wait_while(&mut count, |count| *count != 0);
+    }
+
+    /// returns `false` if the count is not zero within `timeout`.
+    #[inline]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.wait_deadline(Instant::now() + timeout)
+    }
+
+    /// returns `false` if the count is not zero before `deadline`.
+    pub fn wait_deadline(&self, deadline: Instant) -> bool {
+        let mut count = self.count.lock();
+        self.condvar
+            .wait_while_until(&mut count, |count| *count != 0, deadline);
+        *count == 0
+    }
+
+    /// waits until the count is zero without blocking the thread.
+    pub async fn wait_async(&self) {
+        std::future::poll_fn(|cx| {
+            if self.is_released() {
+                return Poll::Ready(());
+            }
+            self.wakers.register(cx.waker());
+            if self.is_released() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let latch = CountDownLatch::new(4);
+        let done = AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    std::thread::sleep(Duration::from_millis(10));
+                    done.fetch_add(1, Ordering::SeqCst);
+                    latch.count_down();
+                });
+            }
+            assert!(!latch.wait_timeout(Duration::ZERO));
+            latch.wait();
+            assert_eq!(done.load(Ordering::SeqCst), 4);
+        });
+        assert!(latch.is_released());
+        latch.count_down();
+        assert_eq!(latch.count(), 0);
+        assert!(latch.wait_timeout(Duration::ZERO));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn async_() {
+        use crate::async_::FutureWait;
+
+        let latch = CountDownLatch::new(2);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(10));
+                latch.count_down();
+                latch.count_down();
+            });
+            latch.wait_async().wait();
+        });
+        assert!(latch.is_released());
+    }
+}
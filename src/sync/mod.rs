@@ -1,5 +1,17 @@
+pub mod barrier;
+pub mod broadcast;
+pub mod clock;
+pub mod latch;
 pub mod mpmc;
+pub mod semaphore;
 pub mod spsc;
 pub mod timer;
+pub mod watch;
 
-pub use timer::TimerPool;
+mod waiters;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use latch::CountDownLatch;
+pub use semaphore::{Semaphore, SemaphorePermit};
+pub use timer::{TimerBackend, TimerHandle, TimerPool};
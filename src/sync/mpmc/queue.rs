@@ -1,153 +1,530 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        Arc,
+        atomic::{self, AtomicUsize},
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
 
 use crossbeam_queue::{ArrayQueue, SegQueue};
 
-#[repr(transparent)]
-pub struct BoundedReceiver<T> {
-    queue: Arc<ArrayQueue<T>>,
+use crate::sync::waiters::{Sleepers, WakerSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvError {
+    #[error("Queue channel is disconnected")]
+    Disconnected,
 }
 
-impl<T> BoundedReceiver<T> {
-    #[inline]
-    pub fn try_recv(&self) -> Option<T> {
-        self.queue.pop()
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TryRecvError {
+    #[error("Queue channel is disconnected")]
+    Disconnected,
+    #[error("Queue channel is empty")]
+    Empty,
+}
 
-    pub fn try_iter<'a>(&'a self) -> impl Iterator<Item = T> + 'a {
-        struct TryIter<'a, T> {
-            queue: &'a ArrayQueue<T>,
-        }
-        impl<'a, T> Iterator for TryIter<'a, T> {
-            type Item = T;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvTimeoutError {
+    #[error("Queue channel is disconnected")]
+    Disconnected,
+    #[error("Queue channel receiving timeout")]
+    Timeout,
+}
 
-            #[inline]
-            fn next(&mut self) -> Option<T> {
-                self.queue.pop()
-            }
-        }
-        TryIter { queue: &self.queue }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum SendError<T> {
+    #[error("Queue channel is disconnected")]
+    Disconnected(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TrySendError<T> {
+    #[error("Queue channel is disconnected")]
+    Disconnected(T),
+    #[error("Queue channel is full")]
+    Full(T),
+}
+
+trait Queue {
+    type Item;
+
+    fn pop(&self) -> Option<Self::Item>;
+}
+
+impl<T> Queue for ArrayQueue<T> {
+    type Item = T;
 
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.queue.capacity()
+    fn pop(&self) -> Option<T> {
+        ArrayQueue::pop(self)
     }
 }
 
-impl<T> Clone for BoundedReceiver<T> {
+impl<T> Queue for SegQueue<T> {
+    type Item = T;
+
     #[inline]
-    fn clone(&self) -> Self {
-        Self {
-            queue: self.queue.clone(),
-        }
+    fn pop(&self) -> Option<T> {
+        SegQueue::pop(self)
     }
 }
 
-#[repr(transparent)]
-pub struct BoundedSender<T> {
-    queue: Arc<ArrayQueue<T>>,
+struct Shared<Q> {
+    queue: Q,
+    /// async receivers.
+    wakers: WakerSet,
+    /// blocking receivers.
+    sleepers: Sleepers,
+    num_senders: AtomicUsize,
+    num_receivers: AtomicUsize,
 }
 
-impl<T> BoundedSender<T> {
+impl<Q: Queue> Shared<Q> {
     #[inline]
-    pub fn send(&self, value: T) -> Result<(), T> {
-        self.queue.push(value)
+    fn new(queue: Q) -> Arc<Self> {
+        Arc::new(Self {
+            queue,
+            wakers: WakerSet::new(),
+            sleepers: Sleepers::new(),
+            num_senders: AtomicUsize::new(1),
+            num_receivers: AtomicUsize::new(1),
+        })
     }
 
-    /// if the queue is full, the oldest element is replaced and returned.
     #[inline]
-    pub fn force_send(&self, value: T) -> Option<T> {
-        self.queue.force_push(value)
+    fn receivers_dropped(&self) -> bool {
+        self.num_receivers.load(atomic::Ordering::Acquire) == 0
     }
 
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.queue.capacity()
+    fn notify_one(&self) {
+        self.sleepers.notify_one();
+        self.wakers.wake_all();
+    }
+
+    fn try_recv(&self) -> Result<Q::Item, TryRecvError> {
+        if let Some(value) = self.queue.pop() {
+            return Ok(value);
+        }
+        if self.num_senders.load(atomic::Ordering::Acquire) != 0 {
+            return Err(TryRecvError::Empty);
+        }
+        // values sent before the last sender is dropped are still received.
+        self.queue.pop().ok_or(TryRecvError::Disconnected)
+    }
+
+    fn recv_deadline(&self, deadline: Option<Instant>) -> Result<Q::Item, RecvTimeoutError> {
+        self.sleepers
+            .wait_until(deadline, || match self.try_recv() {
+                Ok(value) => Some(Ok(value)),
+                Err(TryRecvError::Disconnected) => Some(Err(RecvTimeoutError::Disconnected)),
+                Err(TryRecvError::Empty) => None,
+            })
+            .unwrap_or(Err(RecvTimeoutError::Timeout))
+    }
+
+    async fn recv_async(&self) -> Result<Q::Item, RecvError> {
+        let poll_recv = || match self.try_recv() {
+            Ok(value) => Poll::Ready(Ok(value)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(Err(RecvError::Disconnected)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        };
+        std::future::poll_fn(|cx| {
+            if let Poll::Ready(r) = poll_recv() {
+                return Poll::Ready(r);
+            }
+            self.wakers.register(cx.waker());
+            poll_recv()
+        })
+        .await
+    }
+
+    #[inline]
+    fn add_sender(self: &Arc<Self>) -> Arc<Self> {
+        self.num_senders.fetch_add(1, atomic::Ordering::Relaxed);
+        self.clone()
     }
-}
 
-impl<T> Clone for BoundedSender<T> {
     #[inline]
-    fn clone(&self) -> Self {
-        Self {
-            queue: self.queue.clone(),
+    fn add_receiver(self: &Arc<Self>) -> Arc<Self> {
+        self.num_receivers.fetch_add(1, atomic::Ordering::Relaxed);
+        self.clone()
+    }
+
+    fn remove_sender(&self) {
+        if self.num_senders.fetch_sub(1, atomic::Ordering::Release) == 1 {
+            self.sleepers.notify_all();
+            self.wakers.wake_all();
         }
     }
+
+    #[inline]
+    fn remove_receiver(&self) {
+        self.num_receivers.fetch_sub(1, atomic::Ordering::Release);
+    }
 }
 
-#[repr(transparent)]
-pub struct UnboundedReceiver<T> {
-    queue: Arc<SegQueue<T>>,
+macro_rules! impl_receiver {
+    ($receiver:ident, $queue:ident) => {
+        impl<T> $receiver<T> {
+            /// returns `Disconnected` if the queue is empty and all senders are dropped.
+            #[inline]
+            pub fn try_recv(&self) -> Result<T, TryRecvError> {
+                self.shared.try_recv()
+            }
+
+            /// blocking until a value is received or all senders are dropped.
+            #[inline]
+            pub fn recv(&self) -> Result<T, RecvError> {
+                self.shared
+                    .recv_deadline(None)
+                    .map_err(|_| RecvError::Disconnected)
+            }
+
+            #[inline]
+            pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+                self.recv_deadline(Instant::now() + timeout)
+            }
+
+            #[inline]
+            pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+                self.shared.recv_deadline(Some(deadline))
+            }
+
+            /// receives a value without blocking the thread.
+            ///
+            /// every waiting receiver is woken up when a value is sent.
+            #[inline]
+            pub async fn recv_async(&self) -> Result<T, RecvError> {
+                self.shared.recv_async().await
+            }
+
+            pub fn try_iter<'a>(&'a self) -> impl Iterator<Item = T> + 'a {
+                struct TryIter<'a, T> {
+                    queue: &'a $queue<T>,
+                }
+                impl<'a, T> Iterator for TryIter<'a, T> {
+                    type Item = T;
+
+                    #[inline]
+                    fn next(&mut self) -> Option<T> {
+                        self.queue.pop()
+                    }
+                }
+                TryIter {
+                    queue: &self.shared.queue,
+                }
+            }
+
+            /// blocking iterator, ends when all senders are dropped.
+            pub fn iter<'a>(&'a self) -> impl Iterator<Item = T> + 'a {
+                std::iter::from_fn(|| self.recv().ok())
+            }
+
+            #[inline]
+            pub fn len(&self) -> usize {
+                self.shared.queue.len()
+            }
+
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// `true` if all senders are dropped.
+            #[inline]
+            pub fn is_disconnected(&self) -> bool {
+                self.sender_count() == 0
+            }
+
+            #[inline]
+            pub fn sender_count(&self) -> usize {
+                self.shared.num_senders.load(atomic::Ordering::Acquire)
+            }
+
+            #[inline]
+            pub fn receiver_count(&self) -> usize {
+                self.shared.num_receivers.load(atomic::Ordering::Acquire)
+            }
+        }
+
+        impl<T> Clone for $receiver<T> {
+            #[inline]
+            fn clone(&self) -> Self {
+                Self {
+                    shared: self.shared.add_receiver(),
+                }
+            }
+        }
+
+        impl<T> Drop for $receiver<T> {
+            #[inline]
+            fn drop(&mut self) {
+                self.shared.remove_receiver();
+            }
+        }
+    };
 }
 
-impl<T> UnboundedReceiver<T> {
-    #[inline]
-    pub fn try_recv(&self) -> Option<T> {
-        self.queue.pop()
-    }
+macro_rules! impl_sender {
+    ($sender:ident) => {
+        impl<T> $sender<T> {
+            /// `true` if all receivers are dropped.
+            #[inline]
+            pub fn is_disconnected(&self) -> bool {
+                self.receiver_count() == 0
+            }
 
-    pub fn try_iter<'a>(&'a self) -> impl Iterator<Item = T> + 'a {
-        struct TryIter<'a, T> {
-            queue: &'a SegQueue<T>,
+            #[inline]
+            pub fn sender_count(&self) -> usize {
+                self.shared.num_senders.load(atomic::Ordering::Acquire)
+            }
+
+            #[inline]
+            pub fn receiver_count(&self) -> usize {
+                self.shared.num_receivers.load(atomic::Ordering::Acquire)
+            }
         }
-        impl<'a, T> Iterator for TryIter<'a, T> {
-            type Item = T;
 
+        impl<T> Clone for $sender<T> {
             #[inline]
-            fn next(&mut self) -> Option<T> {
-                self.queue.pop()
+            fn clone(&self) -> Self {
+                Self {
+                    shared: self.shared.add_sender(),
+                }
             }
         }
-        TryIter { queue: &self.queue }
-    }
+
+        impl<T> Drop for $sender<T> {
+            #[inline]
+            fn drop(&mut self) {
+                self.shared.remove_sender();
+            }
+        }
+    };
 }
 
-impl<T> Clone for UnboundedReceiver<T> {
+#[repr(transparent)]
+pub struct BoundedReceiver<T> {
+    shared: Arc<Shared<ArrayQueue<T>>>,
+}
+
+impl_receiver!(BoundedReceiver, ArrayQueue);
+
+impl<T> BoundedReceiver<T> {
     #[inline]
-    fn clone(&self) -> Self {
-        Self {
-            queue: self.queue.clone(),
-        }
+    pub fn capacity(&self) -> usize {
+        self.shared.queue.capacity()
     }
 }
 
 #[repr(transparent)]
-pub struct UnboundedSender<T> {
-    queue: Arc<SegQueue<T>>,
+pub struct BoundedSender<T> {
+    shared: Arc<Shared<ArrayQueue<T>>>,
 }
 
-impl<T> UnboundedSender<T> {
+impl_sender!(BoundedSender);
+
+impl<T> BoundedSender<T> {
+    /// returns `Disconnected` if all receivers are dropped, then the value is not sent.
+    #[inline]
+    pub fn send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.shared.receivers_dropped() {
+            return Err(TrySendError::Disconnected(value));
+        }
+        self.shared.queue.push(value).map_err(TrySendError::Full)?;
+        self.shared.notify_one();
+        Ok(())
+    }
+
+    /// if the queue is full, the oldest element is replaced and returned.
     #[inline]
-    pub fn send(&self, value: T) {
-        self.queue.push(value)
+    pub fn force_send(&self, value: T) -> Result<Option<T>, SendError<T>> {
+        if self.shared.receivers_dropped() {
+            return Err(SendError::Disconnected(value));
+        }
+        let r = self.shared.queue.force_push(value);
+        self.shared.notify_one();
+        Ok(r)
     }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.queue.capacity()
+    }
+}
+
+#[repr(transparent)]
+pub struct UnboundedReceiver<T> {
+    shared: Arc<Shared<SegQueue<T>>>,
+}
+
+impl_receiver!(UnboundedReceiver, SegQueue);
+
+#[repr(transparent)]
+pub struct UnboundedSender<T> {
+    shared: Arc<Shared<SegQueue<T>>>,
 }
 
-impl<T> Clone for UnboundedSender<T> {
+impl_sender!(UnboundedSender);
+
+impl<T> UnboundedSender<T> {
+    /// returns `Disconnected` if all receivers are dropped, then the value is not sent.
     #[inline]
-    fn clone(&self) -> Self {
-        Self {
-            queue: self.queue.clone(),
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.shared.receivers_dropped() {
+            return Err(SendError::Disconnected(value));
         }
+        self.shared.queue.push(value);
+        self.shared.notify_one();
+        Ok(())
     }
 }
 
 /// concurrent queue channel with bounded capacity.
 pub fn bounded<T: Send>(capacity: usize) -> (BoundedSender<T>, BoundedReceiver<T>) {
-    let queue = Arc::new(ArrayQueue::new(capacity));
+    let shared = Shared::new(ArrayQueue::new(capacity));
     let sender = BoundedSender {
-        queue: queue.clone(),
+        shared: shared.clone(),
     };
-    let receiver = BoundedReceiver { queue };
+    let receiver = BoundedReceiver { shared };
     (sender, receiver)
 }
 
 /// concurrent queue channel with unbounded capacity.
 pub fn unbounded<T: Send>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
-    let queue = Arc::new(SegQueue::new());
+    let shared = Shared::new(SegQueue::new());
     let sender = UnboundedSender {
-        queue: queue.clone(),
+        shared: shared.clone(),
     };
-    let receiver = UnboundedReceiver { queue };
+    let receiver = UnboundedReceiver { shared };
     (sender, receiver)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "thread_async")]
+    fn recv_async() {
+        use crate::thread::AsyncThreadPool;
+
+        let (sender, receiver) = unbounded::<usize>();
+        let (r_sender, r_receiver) = bounded::<usize>(8);
+        let thread_pool = AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        (0..4).for_each(|_| {
+            let receiver = receiver.clone();
+            let r_sender = r_sender.clone();
+            thread_pool.add_task(async move {
+                let value = receiver.recv_async().await.expect("disconnected");
+                let _ = r_sender.send(value * 10);
+            });
+        });
+        (0..4).for_each(|i| assert_eq!(sender.send(i), Ok(())));
+        let mut r: Vec<_> = crate::async_::block_on(async {
+            let mut r = Vec::new();
+            for _ in 0..4 {
+                r.push(r_receiver.recv_async().await.expect("disconnected"));
+            }
+            r
+        });
+        r.sort();
+        assert_eq!(r, [0, 10, 20, 30]);
+    }
+
+    #[test]
+    fn recv() {
+        let (sender, receiver) = bounded::<usize>(4);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        let sum = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let receiver = receiver.clone();
+                    s.spawn(move || receiver.iter().sum::<usize>())
+                })
+                .collect();
+            assert_eq!(receiver.receiver_count(), 5);
+            (0..4).for_each(|_| {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        let mut value = i;
+                        while let Err(TrySendError::Full(v)) = sender.send(value) {
+                            value = v;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            });
+            std::thread::sleep(Duration::from_millis(10));
+            drop(sender);
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("receiver panic"))
+                .sum::<usize>()
+        });
+        assert_eq!(sum, 4 * 999 * 1000 / 2);
+        assert!(receiver.is_disconnected());
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn disconnected() {
+        let (sender, receiver) = unbounded::<usize>();
+        assert_eq!(sender.send(1), Ok(()));
+        let sender2 = sender.clone();
+        assert_eq!(receiver.sender_count(), 2);
+        drop(sender);
+        assert!(!receiver.is_disconnected());
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(10));
+                drop(sender2);
+            });
+            // values sent before the disconnection are still received.
+            assert_eq!(receiver.recv(), Ok(1));
+            assert_eq!(
+                receiver.recv_timeout(Duration::from_secs(10)),
+                Err(RecvTimeoutError::Disconnected)
+            );
+        });
+
+        let (sender, receiver) = unbounded::<usize>();
+        assert!(!sender.is_disconnected());
+        drop(receiver);
+        assert!(sender.is_disconnected());
+        assert_eq!(sender.send(1), Err(SendError::Disconnected(1)));
+
+        let (sender, receiver) = bounded::<usize>(1);
+        assert_eq!(sender.send(1), Ok(()));
+        assert_eq!(sender.send(2), Err(TrySendError::Full(2)));
+        assert_eq!(sender.force_send(2), Ok(Some(1)));
+        drop(receiver);
+        assert_eq!(sender.send(3), Err(TrySendError::Disconnected(3)));
+        assert_eq!(sender.force_send(3), Err(SendError::Disconnected(3)));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn recv_async_disconnected() {
+        let (sender, receiver) = unbounded::<usize>();
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                std::thread::sleep(Duration::from_millis(10));
+                assert_eq!(sender.send(1), Ok(()));
+            });
+            crate::async_::block_on(async {
+                assert_eq!(receiver.recv_async().await, Ok(1));
+                assert_eq!(receiver.recv_async().await, Err(RecvError::Disconnected));
+            });
+        });
+    }
+}
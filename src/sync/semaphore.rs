@@ -0,0 +1,184 @@
+use std::{
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use super::waiters::WakerSet;
+
+/// counting semaphore for both threads and async tasks.
+pub struct Semaphore {
+    permits: Mutex<usize>,
+    condvar: Condvar,
+    wakers: WakerSet,
+}
+
+impl Semaphore {
+    #[inline]
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: Mutex::new(permits),
+            condvar: Condvar::new(),
+            wakers: WakerSet::new(),
+        }
+    }
+
+    /// blocking until a permit is available.
+    pub fn acquire(&self) -> SemaphorePermit<'_> {
+        let mut permits = self.permits.lock();
+        self.condvar
+            .wait_while(&mut permits, |permits| *permits == 0);
+        *permits -= 1;
+        SemaphorePermit { semaphore: self }
+    }
+
+    /// returns `None` if no permit is available within `timeout`.
+    #[inline]
+    pub fn acquire_timeout(&self, timeout: Duration) -> Option<SemaphorePermit<'_>> {
+        self.acquire_deadline(Instant::now() + timeout)
+    }
+
+    /// returns `None` if no permit is available before `deadline`.
+    pub fn acquire_deadline(&self, deadline: Instant) -> Option<SemaphorePermit<'_>> {
+        let mut permits = self.permits.lock();
+        self.condvar
+            .wait_while_until(&mut permits, |permits| *permits == 0, deadline);
+        (*permits != 0).then(|| {
+            *permits -= 1;
+            SemaphorePermit { semaphore: self }
+        })
+    }
+
+    /// acquires a permit without blocking the thread.
+    ///
+    /// every waiting task is woken up when a permit is released.
+    pub async fn acquire_async(&self) -> SemaphorePermit<'_> {
+        std::future::poll_fn(|cx| {
+            if let Some(permit) = self.try_acquire() {
+                return Poll::Ready(permit);
+            }
+            self.wakers.register(cx.waker());
+            self.try_acquire().map_or(Poll::Pending, Poll::Ready)
+        })
+        .await
+    }
+
+    #[inline]
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let mut permits = self.permits.lock();
+        (*permits != 0).then(|| {
+            *permits -= 1;
+            SemaphorePermit { semaphore: self }
+        })
+    }
+
+    #[inline]
+    pub fn available_permits(&self) -> usize {
+        *self.permits.lock()
+    }
+
+    pub fn add_permits(&self, n: usize) {
+        {
+            let mut permits = self.permits.lock();
+            *permits += n;
+            self.condvar.notify_all();
+        }
+        self.wakers.wake_all();
+    }
+}
+
+/// released when dropped.
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl SemaphorePermit<'_> {
+    /// the permit is never released.
+    #[inline]
+    pub fn forget(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        {
+            let mut permits = self.semaphore.permits.lock();
+            *permits += 1;
+            self.semaphore.condvar.notify_one();
+        }
+        self.semaphore.wakers.wake_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let semaphore = Semaphore::new(2);
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let _permit = semaphore.acquire();
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(n, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(max_running.into_inner(), 2);
+        assert_eq!(semaphore.available_permits(), 2);
+
+        let p0 = semaphore.try_acquire();
+        let p1 = semaphore.acquire_timeout(Duration::from_millis(10));
+        assert!(p0.is_some() && p1.is_some());
+        assert!(semaphore.try_acquire().is_none());
+        assert!(
+            semaphore
+                .acquire_timeout(Duration::from_millis(10))
+                .is_none()
+        );
+        p1.map(SemaphorePermit::forget);
+        drop(p0);
+        assert_eq!(semaphore.available_permits(), 1);
+        semaphore.add_permits(2);
+        assert_eq!(semaphore.available_permits(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "thread_async")]
+    fn async_() {
+        use std::sync::Arc;
+
+        let semaphore = Arc::new(Semaphore::new(3));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let pool = crate::thread::AsyncThreadPool::new(4.try_into().expect("non-zero"));
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let (semaphore, running, max_running) =
+                    (semaphore.clone(), running.clone(), max_running.clone());
+                pool.spawn(async move {
+                    let _permit = semaphore.acquire_async().await;
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(n, Ordering::SeqCst);
+                    crate::async_::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .for_each(|h| h.join().expect("task panic"));
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(semaphore.available_permits(), 3);
+    }
+}
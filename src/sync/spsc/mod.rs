@@ -1,5 +1,7 @@
 pub mod once;
 pub mod swap;
+pub mod triple;
 
 pub use once::*;
 pub use swap::*;
+pub use triple::*;
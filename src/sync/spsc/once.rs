@@ -3,6 +3,7 @@ use std::{
     mem::MaybeUninit,
     ptr::NonNull,
     sync::atomic::{self, AtomicU8},
+    task::{Poll, Waker},
     thread::Thread,
     time::Instant,
 };
@@ -13,6 +14,7 @@ pub struct OnceInner<T> {
     lock: RawMutex,
     value: UnsafeCell<Option<T>>,
     thread: UnsafeCell<Option<Thread>>,
+    waker: UnsafeCell<Option<Waker>>,
 
     state: AtomicU8,
 }
@@ -26,6 +28,7 @@ impl<T> OnceInner<T> {
             lock: RawMutex::INIT,
             value: UnsafeCell::new(None),
             thread: UnsafeCell::new(None),
+            waker: UnsafeCell::new(None),
             state: AtomicU8::new(if inplace { Self::INPLACE_BIT } else { 0 }),
         }
     }
@@ -73,6 +76,28 @@ impl<T> OnceReceiver<T> {
         }
     }
 
+    /// Receive the one-time value without blocking the thread. The returned future completes
+    /// when the value is available.
+    pub async fn recv_async(self) -> T {
+        std::future::poll_fn(move |cx| {
+            // capture the whole `self` rather than `self.inner`, which is not `Send`.
+            let this = &self;
+            let inner = unsafe { this.inner.as_ref() };
+            inner.lock.lock();
+            let value = unsafe { &mut *inner.value.get() }.take();
+            if value.is_none() {
+                let waker = unsafe { &mut *inner.waker.get() };
+                match waker {
+                    Some(waker) => waker.clone_from(cx.waker()),
+                    None => *waker = Some(cx.waker().clone()),
+                }
+            }
+            unsafe { inner.lock.unlock() };
+            value.map_or(Poll::Pending, Poll::Ready)
+        })
+        .await
+    }
+
     /// Try to receive the one-time value. This function returns `Ok(value)` if the value is
     /// available, or `Err(self)` if the value is not available yet.
     pub fn try_recv(self) -> Result<T, Self> {
@@ -163,8 +188,10 @@ impl<T> OnceSender<T> {
         inner.lock.lock();
         unsafe { *inner.value.get() = Some(value) };
         let thread = unsafe { &mut *inner.thread.get() }.take();
+        let waker = unsafe { &mut *inner.waker.get() }.take();
         unsafe { inner.lock.unlock() };
         thread.map(|thread| thread.unpark());
+        waker.map(Waker::wake);
     }
 }
 
@@ -261,6 +288,19 @@ mod tests {
         receiver.try_recv_timeout(Duration::from_secs(10)).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "async")]
+    fn recv_async() {
+        use crate::async_::FutureWait;
+
+        let (sender, receiver) = once::<String>();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            sender.send("world".into());
+        });
+        assert_eq!(receiver.recv_async().wait(), "world");
+    }
+
     #[test]
     fn inplace() {
         let mut inner = MaybeUninit::uninit();
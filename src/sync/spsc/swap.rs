@@ -1,6 +1,13 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        Arc,
+        atomic::{self, AtomicBool},
+    },
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
 
-use parking_lot::{Condvar, Mutex};
+use parking_lot::{Condvar, Mutex, MutexGuard};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
 pub enum RecvError {
@@ -16,17 +23,85 @@ pub enum TryRecvError {
     Empty,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvTimeoutError {
+    #[error("Swap channel is disconnected")]
+    Disconnected,
+    #[error("Swap channel receiving timeout")]
+    Timeout,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
 pub enum SendError<T> {
     #[error("Swap channel is disconnected")]
     Disconnected(T),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum TrySendError<T> {
+    #[error("Swap channel is disconnected")]
+    Disconnected(T),
+    #[error("Swap channel is full")]
+    Full(T),
+}
+
 struct SwapInner<T> {
     buf: Mutex<Vec<T>>,
+    /// notified when `buf` is not empty.
     condvar: Condvar,
+    /// notified when `buf` is not full.
+    space: Condvar,
+    waker: Mutex<Option<Waker>>,
+    /// max length of `buf`, `None` if unbounded.
+    bound: Option<usize>,
+    /// set when the sender is dropped, modified only while `buf` is locked.
+    closed: AtomicBool,
+    /// set when the receiver is dropped, modified only while `buf` is locked.
+    receiver_closed: AtomicBool,
 }
 
+impl<T> SwapInner<T> {
+    #[inline]
+    fn is_full(&self, buf: &[T]) -> bool {
+        self.bound.is_some_and(|bound| buf.len() >= bound)
+    }
+
+    /// swaps the empty local buffer of the receiver with the channel buffer.
+    #[inline]
+    fn take_buf(&self, local: &mut Vec<T>, buf: &mut Vec<T>) {
+        core::mem::swap(local, buf);
+        self.space.notify_one();
+    }
+
+    /// blocking until `buf` is not empty, returns `Disconnected` only if `buf` is empty and the
+    /// sender is dropped, so values sent before that are still received.
+    fn lock_not_empty(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<MutexGuard<'_, Vec<T>>, RecvTimeoutError> {
+        let mut buf = self.buf.lock();
+        let mut is_empty =
+            |buf: &mut Vec<T>| buf.is_empty() && !self.closed.load(atomic::Ordering::Relaxed);
+        match deadline {
+            Some(deadline) => {
+                self.condvar
+                    .wait_while_until(&mut buf, &mut is_empty, deadline);
+            }
+            None => self.condvar.wait_while(&mut buf, &mut is_empty),
+        }
+        if !buf.is_empty() {
+            return Ok(buf);
+        }
+        Err(if self.closed.load(atomic::Ordering::Relaxed) {
+            RecvTimeoutError::Disconnected
+        } else {
+            RecvTimeoutError::Timeout
+        })
+    }
+}
+
+/// the receiver takes the whole channel buffer at once, `recv` and `try_recv` pop the newest
+/// value of it first, [`recv_into`](Self::recv_into) keeps the sending order.
 pub struct SwapReceiver<T> {
     inner: Arc<SwapInner<T>>,
     buf: Vec<T>,
@@ -34,7 +109,7 @@ pub struct SwapReceiver<T> {
 
 #[inline]
 fn swap_disconnected<T>(inner: &Arc<SwapInner<T>>) -> bool {
-    Arc::strong_count(inner) == 1
+    Arc::strong_count(inner) == 1 || inner.closed.load(atomic::Ordering::Acquire)
 }
 
 impl<T> SwapReceiver<T> {
@@ -54,6 +129,73 @@ impl<T> SwapReceiver<T> {
         Ok(unsafe { self.buf.pop().unwrap_unchecked() })
     }
 
+    #[inline]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline(Instant::now() + timeout)
+    }
+
+    pub fn recv_deadline(&mut self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        if let Some(x) = self.buf.pop() {
+            return Ok(x);
+        }
+        {
+            let mut buf = self.inner.lock_not_empty(Some(deadline))?;
+            self.inner.take_buf(&mut self.buf, &mut buf);
+        }
+        Ok(unsafe { self.buf.pop().unwrap_unchecked() })
+    }
+
+    /// blocking until not empty, then moves all received values into `buf` in sending order,
+    /// returns the number of them.
+    ///
+    /// if `buf` and the local buffer are empty, `buf` is swapped with the channel buffer, so no
+    /// value is moved and the allocation of `buf` is reused by the channel.
+    pub fn recv_into(&mut self, buf: &mut Vec<T>) -> Result<usize, RecvError> {
+        if !self.buf.is_empty() {
+            // the local buffer is older than the channel buffer.
+            let len = self.buf.len();
+            buf.append(&mut self.buf);
+            return Ok(len);
+        }
+        let mut shared = self
+            .inner
+            .lock_not_empty(None)
+            .map_err(|_| RecvError::Disconnected)?;
+        let len = shared.len();
+        if buf.is_empty() {
+            core::mem::swap(buf, &mut *shared);
+        } else {
+            buf.append(&mut shared);
+        }
+        self.inner.space.notify_one();
+        Ok(len)
+    }
+
+    /// receive without blocking the thread.
+    pub async fn recv_async(&mut self) -> Result<T, RecvError> {
+        std::future::poll_fn(|cx| {
+            if let Some(x) = self.buf.pop() {
+                return Poll::Ready(Ok(x));
+            }
+            let mut buf = self.inner.buf.lock();
+            if !buf.is_empty() {
+                self.inner.take_buf(&mut self.buf, &mut buf);
+                return Poll::Ready(Ok(unsafe { self.buf.pop().unwrap_unchecked() }));
+            }
+            if swap_disconnected(&self.inner) {
+                return Poll::Ready(Err(RecvError::Disconnected));
+            }
+            // register while `buf` is locked, so the next `send` must see the waker.
+            let mut waker = self.inner.waker.lock();
+            match &mut *waker {
+                Some(waker) => waker.clone_from(cx.waker()),
+                None => *waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
     pub fn try_iter<'a>(&'a mut self) -> impl Iterator<Item = T> + 'a {
         struct TryIter<'a, T> {
             receiver: &'a mut SwapReceiver<T>,
@@ -86,62 +228,176 @@ impl<T> SwapReceiver<T> {
     ///
     /// if returns `Ok`, `self.buf` is not empty.
     fn swap_buf(&mut self) -> Result<(), RecvError> {
-        if self.disconnected() {
-            return Err(RecvError::Disconnected);
-        }
-        {
-            let mut buf = self.inner.buf.lock();
-            self.inner
-                .condvar
-                .wait_while(&mut buf, |buf| buf.is_empty());
-            core::mem::swap(&mut self.buf, &mut *buf);
-        }
+        let mut buf = self
+            .inner
+            .lock_not_empty(None)
+            .map_err(|_| RecvError::Disconnected)?;
+        self.inner.take_buf(&mut self.buf, &mut buf);
         Ok(())
     }
 
     /// if returns `Ok`, `self.buf` is not empty.
     fn try_swap_buf(&mut self) -> Result<(), TryRecvError> {
-        if self.disconnected() {
-            return Err(TryRecvError::Disconnected);
-        }
-        {
-            let mut buf = self.inner.buf.lock();
-            if buf.is_empty() {
-                return Err(TryRecvError::Empty);
-            }
-            core::mem::swap(&mut self.buf, &mut *buf);
+        let mut buf = self.inner.buf.lock();
+        if buf.is_empty() {
+            return Err(if self.inner.closed.load(atomic::Ordering::Relaxed) {
+                TryRecvError::Disconnected
+            } else {
+                TryRecvError::Empty
+            });
         }
+        self.inner.take_buf(&mut self.buf, &mut buf);
         Ok(())
     }
 }
 
+impl<T> Drop for SwapReceiver<T> {
+    fn drop(&mut self) {
+        let _buf = self.inner.buf.lock();
+        self.inner
+            .receiver_closed
+            .store(true, atomic::Ordering::Release);
+        self.inner.space.notify_one();
+    }
+}
+
 pub struct SwapSender<T> {
     inner: Arc<SwapInner<T>>,
 }
 
 impl<T> SwapSender<T> {
+    /// blocking while the channel is full.
     pub fn send(&self, value: T) -> Result<(), SendError<T>> {
         if self.disconnected() {
             return Err(SendError::Disconnected(value));
         }
         {
             let mut buf = self.inner.buf.lock();
+            if !self.wait_space(&mut buf) {
+                return Err(SendError::Disconnected(value));
+            }
             buf.push(value);
             self.inner.condvar.notify_one();
         }
+        self.wake();
         Ok(())
     }
 
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.disconnected() {
+            return Err(TrySendError::Disconnected(value));
+        }
+        {
+            let mut buf = self.inner.buf.lock();
+            if self.inner.is_full(&buf) {
+                return Err(TrySendError::Full(value));
+            }
+            buf.push(value);
+            self.inner.condvar.notify_one();
+        }
+        self.wake();
+        Ok(())
+    }
+
+    /// sends all values of `iter` with the channel locked once, unless it becomes full.
+    ///
+    /// blocking while the channel is full, the values not sent are returned if disconnected.
+    pub fn send_batch<I: IntoIterator<Item = T>>(
+        &self,
+        iter: I,
+    ) -> Result<(), SendError<I::IntoIter>> {
+        let mut iter = iter.into_iter();
+        if self.disconnected() {
+            return Err(SendError::Disconnected(iter));
+        }
+        let r = {
+            let mut buf = self.inner.buf.lock();
+            buf.reserve(iter.size_hint().0);
+            let r = loop {
+                if self.inner.is_full(&buf) {
+                    // let the receiver take the values sent so far.
+                    self.inner.condvar.notify_one();
+                    self.wake();
+                    if !self.wait_space(&mut buf) {
+                        break Err(SendError::Disconnected(iter));
+                    }
+                }
+                match iter.next() {
+                    Some(x) => buf.push(x),
+                    None => break Ok(()),
+                }
+            };
+            self.inner.condvar.notify_one();
+            r
+        };
+        self.wake();
+        r
+    }
+
+    /// max number of values pending in the channel, not counting the ones already swapped into
+    /// the receiver, `None` if unbounded.
+    #[inline]
+    pub fn capacity(&self) -> Option<usize> {
+        self.inner.bound
+    }
+
     #[inline]
     pub fn disconnected(&self) -> bool {
         swap_disconnected(&self.inner)
     }
 }
 
+impl<T> SwapSender<T> {
+    /// returns `false` if the receiver is dropped.
+    #[inline]
+    fn wait_space(&self, buf: &mut MutexGuard<Vec<T>>) -> bool {
+        self.inner.space.wait_while(buf, |buf| {
+            self.inner.is_full(buf) && !self.inner.receiver_closed.load(atomic::Ordering::Relaxed)
+        });
+        !self.inner.receiver_closed.load(atomic::Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn wake(&self) {
+        self.inner.waker.lock().take().map(Waker::wake);
+    }
+}
+
+impl<T> Drop for SwapSender<T> {
+    fn drop(&mut self) {
+        {
+            let _buf = self.inner.buf.lock();
+            self.inner.closed.store(true, atomic::Ordering::Release);
+            self.inner.condvar.notify_one();
+        }
+        self.wake();
+    }
+}
+
+/// unbounded swap channel, `capacity` is only the initial capacity of the buffers.
 pub fn swap<T: Send>(capacity: usize) -> (SwapSender<T>, SwapReceiver<T>) {
+    swap_inner(capacity, None)
+}
+
+/// at most `capacity` values are pending in the channel, [`SwapSender::send`] blocks if full.
+///
+/// # Panics
+///
+/// panics if `capacity` is zero.
+pub fn swap_bounded<T: Send>(capacity: usize) -> (SwapSender<T>, SwapReceiver<T>) {
+    assert!(capacity != 0, "`capacity` must be non-zero");
+    swap_inner(capacity, Some(capacity))
+}
+
+fn swap_inner<T: Send>(capacity: usize, bound: Option<usize>) -> (SwapSender<T>, SwapReceiver<T>) {
     let inner = Arc::new(SwapInner {
         buf: Mutex::new(Vec::with_capacity(capacity)),
         condvar: Condvar::new(),
+        space: Condvar::new(),
+        waker: Mutex::new(None),
+        bound,
+        closed: AtomicBool::new(false),
+        receiver_closed: AtomicBool::new(false),
     });
     let sender = SwapSender {
         inner: inner.clone(),
@@ -172,4 +428,103 @@ mod tests {
         assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
         assert_eq!(receiver.disconnected(), true);
     }
+
+    #[test]
+    fn disconnected() {
+        // values sent before the sender is dropped are still received by every method.
+        let (sender, mut receiver) = swap(4);
+        assert_eq!(sender.send(1), Ok(()));
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+
+        let (sender, mut receiver) = swap(4);
+        assert_eq!(sender.send(1), Ok(()));
+        drop(sender);
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+
+        let (sender, mut receiver) = swap(4);
+        assert_eq!(sender.send(1), Ok(()));
+        drop(sender);
+        let timeout = std::time::Duration::from_secs(10);
+        assert_eq!(receiver.recv_timeout(timeout), Ok(1));
+        assert_eq!(
+            receiver.recv_timeout(timeout),
+            Err(RecvTimeoutError::Disconnected)
+        );
+
+        let (sender, mut receiver) = swap(4);
+        assert_eq!(sender.send(1), Ok(()));
+        drop(sender);
+        let mut r = Vec::new();
+        assert_eq!(receiver.recv_into(&mut r), Ok(1));
+        assert_eq!(receiver.recv_into(&mut r), Err(RecvError::Disconnected));
+        assert_eq!(r, [1]);
+    }
+
+    #[test]
+    fn bounded() {
+        let (sender, mut receiver) = swap_bounded(4);
+        assert_eq!(sender.capacity(), Some(4));
+        (0..4).for_each(|i| assert_eq!(sender.try_send(i), Ok(())));
+        assert_eq!(sender.try_send(4), Err(TrySendError::Full(4)));
+        assert_eq!(receiver.recv(), Ok(3));
+        assert_eq!(
+            receiver.recv_timeout(std::time::Duration::from_millis(10)),
+            Ok(2)
+        );
+        // the channel buffer is taken by the receiver.
+        assert_eq!(sender.try_send(4), Ok(()));
+
+        let mut r = Vec::new();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                assert!(sender.send_batch(5..100).is_ok());
+                assert_eq!(sender.send(100), Ok(()));
+            });
+            while r.last() != Some(&100) {
+                assert!(receiver.recv_into(&mut r).is_ok());
+            }
+        });
+        // the local buffer first, then in sending order.
+        assert_eq!(r, (0..2).chain(4..=100).collect::<Vec<_>>());
+        assert_eq!(
+            receiver.recv_timeout(std::time::Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        // a blocked sender is woken up when the receiver is dropped.
+        (0..4).for_each(|i| assert_eq!(sender.try_send(i), Ok(())));
+        std::thread::scope(|s| {
+            let h = s.spawn(|| sender.send_batch([4, 5]).map_err(|_| ()));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            drop(receiver);
+            assert_eq!(h.join().ok(), Some(Err(())));
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn recv_async() {
+        use crate::async_::FutureWait;
+
+        let (sender, mut receiver) = swap(10);
+        let th = std::thread::spawn(move || {
+            (0..10).for_each(|i| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                let _ = sender.send(i);
+            });
+        });
+        let r = async {
+            let mut r = Vec::new();
+            while let Ok(x) = receiver.recv_async().await {
+                r.push(x);
+            }
+            r
+        }
+        .wait();
+        assert_eq!(r.len(), 10);
+        assert!(th.join().is_ok());
+    }
 }
@@ -0,0 +1,188 @@
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        Arc,
+        atomic::{self, AtomicU8},
+    },
+};
+
+struct TripleInner<T> {
+    bufs: [UnsafeCell<T>; 3],
+    /// index of the middle buffer, with [`DIRTY_BIT`](Self::DIRTY_BIT) set if it is published
+    /// but not taken by the reader.
+    middle: AtomicU8,
+}
+
+impl<T> TripleInner<T> {
+    const INDEX_MASK: u8 = 0b11;
+    const DIRTY_BIT: u8 = 0b100;
+}
+
+/// the three buffers are owned by the writer (back), the reader (front) and neither (middle),
+/// so each of them is accessed by one side at a time.
+unsafe impl<T: Send + Sync> Sync for TripleInner<T> {}
+
+#[inline]
+fn triple_disconnected<T>(inner: &Arc<TripleInner<T>>) -> bool {
+    Arc::strong_count(inner) == 1
+}
+
+/// writes into the back buffer, never blocks.
+pub struct TripleWriter<T> {
+    inner: Arc<TripleInner<T>>,
+    back: u8,
+}
+
+impl<T> TripleWriter<T> {
+    /// the back buffer, which holds an old value, it is not seen by the reader until
+    /// [`publish`](Self::publish).
+    #[inline]
+    pub fn write(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.bufs[self.back as usize].get() }
+    }
+
+    /// makes the back buffer the newest value, the reader takes it at the next
+    /// [`TripleReader::read`].
+    ///
+    /// a previously published value not taken by the reader is overwritten later.
+    #[inline]
+    pub fn publish(&mut self) {
+        let old = self.inner.middle.swap(
+            self.back | TripleInner::<T>::DIRTY_BIT,
+            atomic::Ordering::AcqRel,
+        );
+        self.back = old & TripleInner::<T>::INDEX_MASK;
+    }
+
+    /// replaces the back buffer with `value` and publishes it.
+    #[inline]
+    pub fn send(&mut self, value: T) {
+        *self.write() = value;
+        self.publish();
+    }
+
+    #[inline]
+    pub fn disconnected(&self) -> bool {
+        triple_disconnected(&self.inner)
+    }
+}
+
+/// reads the newest published value, never blocks.
+pub struct TripleReader<T> {
+    inner: Arc<TripleInner<T>>,
+    front: u8,
+}
+
+impl<T> TripleReader<T> {
+    /// `true` if a value is published since the last [`update`](Self::update).
+    #[inline]
+    pub fn has_update(&self) -> bool {
+        self.inner.middle.load(atomic::Ordering::Relaxed) & TripleInner::<T>::DIRTY_BIT != 0
+    }
+
+    /// takes the newest published value as the front buffer, returns `false` if there is none.
+    #[inline]
+    pub fn update(&mut self) -> bool {
+        if !self.has_update() {
+            return false;
+        }
+        let old = self.inner.middle.swap(self.front, atomic::Ordering::AcqRel);
+        self.front = old & TripleInner::<T>::INDEX_MASK;
+        true
+    }
+
+    /// updates then returns the front buffer.
+    #[inline]
+    pub fn read(&mut self) -> &mut T {
+        self.update();
+        self.front_mut()
+    }
+
+    /// the front buffer without updating.
+    #[inline]
+    pub fn front(&self) -> &T {
+        unsafe { &*self.inner.bufs[self.front as usize].get() }
+    }
+
+    /// the front buffer without updating, changes are not seen by the writer.
+    #[inline]
+    pub fn front_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.bufs[self.front as usize].get() }
+    }
+
+    #[inline]
+    pub fn disconnected(&self) -> bool {
+        triple_disconnected(&self.inner)
+    }
+}
+
+/// wait-free triple buffer, the reader always gets the newest published value, the values in
+/// between may be skipped.
+///
+/// all three buffers are initialized with `init`.
+pub fn triple<T: Clone + Send + Sync>(init: T) -> (TripleWriter<T>, TripleReader<T>) {
+    triple_with(|| init.clone())
+}
+
+/// like [`triple`], the buffers are initialized with `f`, e.g. preallocated frames.
+pub fn triple_with<T: Send + Sync>(mut f: impl FnMut() -> T) -> (TripleWriter<T>, TripleReader<T>) {
+    let inner = Arc::new(TripleInner {
+        bufs: [(); 3].map(|_| UnsafeCell::new(f())),
+        middle: AtomicU8::new(1),
+    });
+    let writer = TripleWriter {
+        inner: inner.clone(),
+        back: 0,
+    };
+    let reader = TripleReader { inner, front: 2 };
+    (writer, reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let (mut writer, mut reader) = triple(0);
+        assert!(!reader.has_update());
+        assert_eq!(*reader.read(), 0);
+        writer.send(1);
+        writer.send(2);
+        assert!(reader.has_update());
+        assert_eq!(*reader.read(), 2);
+        assert!(!reader.update());
+        assert_eq!(*reader.front(), 2);
+
+        // in-place write.
+        *writer.write() = 3;
+        assert!(!reader.has_update());
+        writer.publish();
+        assert_eq!(*reader.read(), 3);
+        assert!(!writer.disconnected());
+        drop(reader);
+        assert!(writer.disconnected());
+    }
+
+    #[test]
+    fn frames() {
+        const N: usize = 100000;
+        let (mut writer, mut reader) = triple_with(|| vec![0_usize; 256]);
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 1..=N {
+                    writer.write().fill(i);
+                    writer.publish();
+                }
+            });
+            let mut last = 0;
+            while last != N {
+                let frame = reader.read();
+                // a frame is never torn, and never older than the last one.
+                assert!(frame.iter().all(|&x| x == frame[0]));
+                assert!(frame[0] >= last);
+                last = frame[0];
+            }
+        });
+    }
+}
@@ -0,0 +1,774 @@
+use std::{
+    collections::BinaryHeap as Heap,
+    sync::{Arc, OnceLock, Weak},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use super::{Clock, SystemClock};
+
+mod wheel;
+
+use wheel::Wheel;
+
+pub type TimerTaskFn = dyn FnOnce(&mut TimerPool) + Send + 'static;
+
+pub type RepeatingTaskFn = dyn FnMut(&mut TimerPool) + Send + 'static;
+
+enum TimerTask {
+    Once(Box<TimerTaskFn>),
+    Repeating(Box<RepeatingTaskFn>),
+}
+
+struct TimerState {
+    deadline: Instant,
+    /// bumped by [`TimerHandle::reschedule`], a timer of another generation is outdated.
+    generation: u64,
+    /// `None` while a repeating task is running.
+    task: Option<TimerTask>,
+    /// fired or cancelled.
+    done: bool,
+}
+
+/// shared by a timer and its [`TimerHandle`].
+struct TimerEntry {
+    state: Mutex<TimerState>,
+    period: Option<Duration>,
+}
+
+impl TimerEntry {
+    fn new(deadline: Instant, period: Option<Duration>, task: TimerTask) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(TimerState {
+                deadline,
+                generation: 0,
+                task: Some(task),
+                done: false,
+            }),
+            period,
+        })
+    }
+}
+
+pub(crate) struct Timer {
+    deadline: Instant,
+    generation: u64,
+    entry: Arc<TimerEntry>,
+}
+
+impl Timer {
+    #[inline]
+    fn is_outdated(&self) -> bool {
+        let state = self.entry.state.lock();
+        state.done || state.generation != self.generation
+    }
+}
+
+impl PartialEq for Timer {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+/// timers added or rescheduled from outside of the [`TimerPool`], moved into the pool on
+/// [`poll`](TimerPool::poll).
+pub(crate) struct Inbox {
+    timers: Mutex<Vec<Timer>>,
+    /// called after a timer is pushed, e.g. to wake up the thread polling the pool.
+    notify: OnceLock<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl Inbox {
+    #[inline]
+    fn push(&self, timer: Timer) {
+        self.timers.lock().push(timer);
+        self.notify.get().map(|notify| notify());
+    }
+
+    /// `notify` can only be set once.
+    #[inline]
+    pub(crate) fn set_notify(&self, notify: impl Fn() + Send + Sync + 'static) {
+        let _ = self.notify.set(Box::new(notify));
+    }
+
+    pub(crate) fn add_task_boxed(
+        self: &Arc<Self>,
+        deadline: Instant,
+        task: Box<TimerTaskFn>,
+    ) -> TimerHandle {
+        let (timer, handle) = self.new_timer(deadline, None, TimerTask::Once(task));
+        self.push(timer);
+        handle
+    }
+
+    pub(crate) fn add_repeating_boxed(
+        self: &Arc<Self>,
+        start: Instant,
+        period: Duration,
+        task: Box<RepeatingTaskFn>,
+    ) -> TimerHandle {
+        let (timer, handle) = self.new_timer(start, Some(period), TimerTask::Repeating(task));
+        self.push(timer);
+        handle
+    }
+
+    fn new_timer(
+        self: &Arc<Self>,
+        deadline: Instant,
+        period: Option<Duration>,
+        task: TimerTask,
+    ) -> (Timer, TimerHandle) {
+        let entry = TimerEntry::new(deadline, period, task);
+        let handle = TimerHandle {
+            entry: entry.clone(),
+            inbox: Arc::downgrade(self),
+        };
+        let timer = Timer {
+            deadline,
+            generation: 0,
+            entry,
+        };
+        (timer, handle)
+    }
+}
+
+/// handle of a timer in [`TimerPool`] or [`TimerThread`](crate::thread::TimerThread).
+///
+/// dropping the handle doesn't cancel the timer.
+#[derive(Clone)]
+pub struct TimerHandle {
+    entry: Arc<TimerEntry>,
+    inbox: Weak<Inbox>,
+}
+
+impl TimerHandle {
+    /// returns `false` if the timer has fired or been cancelled.
+    ///
+    /// a running repeating task finishes its current run.
+    pub fn cancel(&self) -> bool {
+        let task = {
+            let mut state = self.entry.state.lock();
+            if state.done {
+                return false;
+            }
+            state.done = true;
+            state.task.take()
+        };
+        // drop the task outside the lock.
+        drop(task);
+        true
+    }
+
+    /// moves the timer to `deadline`, returns `false` if the timer has fired or been cancelled.
+    ///
+    /// for a repeating timer, the following ticks are counted from `deadline`.
+    pub fn reschedule(&self, deadline: Instant) -> bool {
+        let Some(inbox) = self.inbox.upgrade() else {
+            return false;
+        };
+        let timer = {
+            let mut state = self.entry.state.lock();
+            if state.done {
+                return false;
+            }
+            state.deadline = deadline;
+            state.generation += 1;
+            Timer {
+                deadline,
+                generation: state.generation,
+                entry: self.entry.clone(),
+            }
+        };
+        inbox.push(timer);
+        true
+    }
+
+    /// returns `false` if the timer has fired or been cancelled.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.entry.state.lock().done
+    }
+
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        self.entry.state.lock().deadline
+    }
+}
+
+/// data structure of [`TimerPool`], see [`TimerPool::with_backend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TimerBackend {
+    /// binary heap, O(log n) insert and pop.
+    #[default]
+    Heap,
+    /// hierarchical timing wheel, O(1) insert and amortized O(1) pop.
+    ///
+    /// better for lots of short timers, especially if most of them are cancelled.
+    Wheel,
+}
+
+enum Timers {
+    Heap(Heap<Timer>),
+    Wheel(Box<Wheel>),
+}
+
+impl Timers {
+    #[inline]
+    fn push(&mut self, timer: Timer) {
+        match self {
+            Self::Heap(heap) => heap.push(timer),
+            Self::Wheel(wheel) => wheel.push(timer),
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&Timer> {
+        match self {
+            Self::Heap(heap) => heap.peek(),
+            Self::Wheel(wheel) => wheel.peek(),
+        }
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<Timer> {
+        match self {
+            Self::Heap(heap) => heap.pop(),
+            Self::Wheel(wheel) => wheel.pop(),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            Self::Heap(heap) => heap.len(),
+            Self::Wheel(wheel) => wheel.len(),
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Heap(heap) => heap.is_empty(),
+            Self::Wheel(wheel) => wheel.is_empty(),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::Heap(heap) => heap.clear(),
+            Self::Wheel(wheel) => wheel.clear(),
+        }
+    }
+
+    fn for_each(&self, f: impl FnMut(&Timer)) {
+        match self {
+            Self::Heap(heap) => heap.iter().for_each(f),
+            Self::Wheel(wheel) => wheel.for_each(f),
+        }
+    }
+}
+
+pub struct TimerPool {
+    timers: Timers,
+    inbox: Arc<Inbox>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for TimerPool {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimerPool {
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let timers = Timers::Heap(Heap::with_capacity(capacity));
+        Self::with_timers(timers, Arc::new(SystemClock))
+    }
+
+    #[inline]
+    pub fn with_backend(backend: TimerBackend) -> Self {
+        Self::with_backend_and_clock(backend, Arc::new(SystemClock))
+    }
+
+    /// the deadlines are reached by `clock`, e.g. a [`ManualClock`](super::ManualClock) in
+    /// tests.
+    #[inline]
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self::with_backend_and_clock(TimerBackend::default(), clock)
+    }
+
+    pub fn with_backend_and_clock(backend: TimerBackend, clock: Arc<dyn Clock>) -> Self {
+        let timers = match backend {
+            TimerBackend::Heap => Timers::Heap(Heap::new()),
+            TimerBackend::Wheel => Timers::Wheel(Box::new(Wheel::new(clock.now()))),
+        };
+        Self::with_timers(timers, clock)
+    }
+
+    #[inline]
+    pub fn add_task(
+        &mut self,
+        deadline: Instant,
+        task: impl FnOnce(&mut Self) + Send + 'static,
+    ) -> TimerHandle {
+        self.add_task_boxed(deadline, Box::new(task))
+    }
+
+    #[inline]
+    pub fn add_task_boxed(&mut self, deadline: Instant, task: Box<TimerTaskFn>) -> TimerHandle {
+        let (timer, handle) = self.inbox.new_timer(deadline, None, TimerTask::Once(task));
+        self.timers.push(timer);
+        handle
+    }
+
+    /// `task` runs at `start`, `start + period`, `start + 2 * period`... until cancelled.
+    ///
+    /// # Panics
+    ///
+    /// panics if `period` is zero.
+    #[inline]
+    pub fn add_repeating(
+        &mut self,
+        start: Instant,
+        period: Duration,
+        task: impl FnMut(&mut Self) + Send + 'static,
+    ) -> TimerHandle {
+        self.add_repeating_boxed(start, period, Box::new(task))
+    }
+
+    /// see [`add_repeating`](Self::add_repeating).
+    pub fn add_repeating_boxed(
+        &mut self,
+        start: Instant,
+        period: Duration,
+        task: Box<RepeatingTaskFn>,
+    ) -> TimerHandle {
+        assert!(!period.is_zero(), "`period` must be non-zero");
+        let (timer, handle) = self
+            .inbox
+            .new_timer(start, Some(period), TimerTask::Repeating(task));
+        self.timers.push(timer);
+        handle
+    }
+
+    /// peek the closest dead line
+    ///
+    /// timers cancelled or rescheduled by [`TimerHandle`] are updated on [`poll`](Self::poll).
+    #[inline]
+    pub fn peek(&self) -> Option<&Instant> {
+        self.timers.peek().map(|timer| &timer.deadline)
+    }
+
+    /// poll the closest timer, returns `Some(task)` if deadline is reached.
+    #[must_use]
+    pub fn poll(&mut self) -> Option<Box<TimerTaskFn>> {
+        self.receive_inbox();
+        let instant_now = self.clock.now();
+        loop {
+            self.remove_outdated();
+            let deadline = self.peek()?;
+            if &instant_now < deadline {
+                return None;
+            }
+            // Safety: self.peek() is `Some`
+            let timer = unsafe { self.timers.pop().unwrap_unchecked() };
+            let mut state = timer.entry.state.lock();
+            // `None` if the task of this repeating timer is running, which polls this pool.
+            let Some(task) = state.task.take() else {
+                continue;
+            };
+            match task {
+                TimerTask::Once(task) => {
+                    state.done = true;
+                    return Some(task);
+                }
+                TimerTask::Repeating(task) => {
+                    drop(state);
+                    let generation = timer.generation;
+                    let entry = timer.entry;
+                    return Some(Box::new(move |timer_pool: &mut Self| {
+                        timer_pool.run_repeating(task, entry, generation)
+                    }));
+                }
+            }
+        }
+    }
+
+    /// if no timer arrived, call [`Clock::sleep_until`] until the first timer arrived.
+    ///
+    /// the default [`SystemClock`] sleeps by [`crate::thread::precise_sleep`].
+    #[inline]
+    pub fn sleep_until_available(&self) {
+        self.peek()
+            .map(|deadline| self.clock.sleep_until(*deadline));
+    }
+
+    #[inline]
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// number of timers, including the cancelled ones not removed yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.timers.len() + self.inbox.timers.lock().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty() && self.inbox.timers.lock().is_empty()
+    }
+}
+
+impl TimerPool {
+    #[inline]
+    fn with_timers(timers: Timers, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            timers,
+            inbox: Arc::new(Inbox {
+                timers: Mutex::new(Vec::new()),
+                notify: OnceLock::new(),
+            }),
+            clock,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn inbox(&self) -> &Arc<Inbox> {
+        &self.inbox
+    }
+
+    /// cancels all repeating timers, so the pool can be empty.
+    pub(crate) fn cancel_repeating(&mut self) {
+        self.receive_inbox();
+        self.timers.for_each(|timer| {
+            let mut state = timer.entry.state.lock();
+            if timer.entry.period.is_some() {
+                state.done = true;
+                state.task = None;
+            }
+        });
+    }
+
+    /// cancels and removes all timers.
+    pub(crate) fn clear(&mut self) {
+        self.receive_inbox();
+        self.timers.for_each(|timer| {
+            let mut state = timer.entry.state.lock();
+            state.done = true;
+            state.task = None;
+        });
+        self.timers.clear();
+    }
+
+    fn receive_inbox(&mut self) {
+        let timers = std::mem::take(&mut *self.inbox.timers.lock());
+        timers.into_iter().for_each(|timer| self.timers.push(timer));
+    }
+
+    fn remove_outdated(&mut self) {
+        while self.timers.peek().is_some_and(Timer::is_outdated) {
+            self.timers.pop();
+        }
+    }
+
+    fn run_repeating(
+        &mut self,
+        mut task: Box<RepeatingTaskFn>,
+        entry: Arc<TimerEntry>,
+        generation: u64,
+    ) {
+        task(self);
+        let mut state = entry.state.lock();
+        if state.done {
+            // cancelled while running.
+            return;
+        }
+        state.task = Some(TimerTask::Repeating(task));
+        if state.generation != generation {
+            // rescheduled while running, the new timer is in the inbox.
+            return;
+        }
+        let period = unsafe { entry.period.unwrap_unchecked() };
+        state.deadline += period;
+        let timer = Timer {
+            deadline: state.deadline,
+            generation,
+            entry: entry.clone(),
+        };
+        drop(state);
+        self.timers.push(timer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let mut timer_pool = TimerPool::new();
+
+        let instant_now = Instant::now();
+
+        for i in 0..=5 {
+            timer_pool.add_task(instant_now + Duration::from_secs(i), move |_| {
+                println!("{i}s passed")
+            });
+        }
+        while !timer_pool.is_empty() {
+            timer_pool.sleep_until_available();
+            while let Some(task) = timer_pool.poll() {
+                task(&mut timer_pool);
+            }
+        }
+        let elapsed = instant_now.elapsed();
+        println!("elapsed: {:#?}", elapsed);
+    }
+
+    fn run(timer_pool: &mut TimerPool) {
+        while !timer_pool.is_empty() {
+            timer_pool.sleep_until_available();
+            while let Some(task) = timer_pool.poll() {
+                task(timer_pool);
+            }
+        }
+    }
+
+    #[test]
+    fn handle() {
+        for backend in [TimerBackend::Heap, TimerBackend::Wheel] {
+            let mut timer_pool = TimerPool::with_backend(backend);
+            let instant_now = Instant::now();
+            let fired = Arc::new(Mutex::new(Vec::new()));
+            let push = |i| {
+                let fired = fired.clone();
+                move |_: &mut TimerPool| fired.lock().push(i)
+            };
+
+            let h0 = timer_pool.add_task(instant_now + Duration::from_millis(10), push(0));
+            let h1 = timer_pool.add_task(instant_now + Duration::from_millis(20), push(1));
+            let h2 = timer_pool.add_task(instant_now + Duration::from_millis(30), push(2));
+            assert!(h0.cancel());
+            assert!(!h0.cancel());
+            assert!(h2.reschedule(instant_now + Duration::from_millis(5)));
+            run(&mut timer_pool);
+            assert_eq!(*fired.lock(), [2, 1]);
+            assert!(!h1.is_active());
+            assert!(!h1.reschedule(Instant::now()));
+        }
+    }
+
+    #[test]
+    fn repeating() {
+        for backend in [TimerBackend::Heap, TimerBackend::Wheel] {
+            let mut timer_pool = TimerPool::with_backend(backend);
+            let instant_now = Instant::now();
+            let period = Duration::from_millis(10);
+            let count = Arc::new(Mutex::new(0));
+            let handle = {
+                let count = count.clone();
+                timer_pool.add_repeating(instant_now, period, move |_| *count.lock() += 1)
+            };
+            let stop = handle.clone();
+            timer_pool.add_task(instant_now + period * 4 + period / 2, move |_| {
+                stop.cancel();
+            });
+            run(&mut timer_pool);
+            assert_eq!(*count.lock(), 5);
+            assert!(!handle.is_active());
+        }
+    }
+
+    #[test]
+    fn manual_clock() {
+        for backend in [TimerBackend::Heap, TimerBackend::Wheel] {
+            let clock = Arc::new(crate::sync::ManualClock::new());
+            let mut timer_pool = TimerPool::with_backend_and_clock(backend, clock.clone());
+            let start = clock.now();
+            let fired = Arc::new(Mutex::new(Vec::new()));
+            for i in (1..=5).rev() {
+                let fired = fired.clone();
+                let clock = clock.clone();
+                timer_pool.add_task(start + Duration::from_secs(i * 3600), move |_| {
+                    fired.lock().push(clock.now() - start)
+                });
+            }
+            let ticks = Arc::new(Mutex::new(0));
+            let t = ticks.clone();
+            let handle =
+                timer_pool.add_repeating(start, Duration::from_secs(3600), move |_| *t.lock() += 1);
+            // not reached yet.
+            clock.advance(Duration::from_secs(3599));
+            while let Some(task) = timer_pool.poll() {
+                task(&mut timer_pool);
+            }
+            assert_eq!(*ticks.lock(), 1);
+            assert!(fired.lock().is_empty());
+
+            let stop = handle.clone();
+            timer_pool.add_task(start + Duration::from_secs(5 * 3600 + 1), move |_| {
+                stop.cancel();
+            });
+            run(&mut timer_pool);
+            let expected: Vec<_> = (1..=5).map(|i| Duration::from_secs(i * 3600)).collect();
+            assert_eq!(*fired.lock(), expected);
+            assert_eq!(*ticks.lock(), 6);
+            assert_eq!(clock.now(), start + Duration::from_secs(5 * 3600 + 1));
+            assert!(start.elapsed() < Duration::from_secs(1));
+        }
+    }
+
+    /// xorshift, deterministic deadlines for the tests.
+    fn deadlines(start: Instant, n: usize, max: Duration) -> Vec<Instant> {
+        let mut x = 0x2545_f491_4f6c_dd1d_u64;
+        (0..n)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                start + Duration::from_nanos(x % max.as_nanos() as u64)
+            })
+            .collect()
+    }
+
+    /// pushes timers of `deadlines`, cancels one of `cancel_every`, then pops all the others.
+    fn drain(mut timers: Timers, deadlines: &[Instant], cancel_every: usize) -> Vec<Instant> {
+        let inbox = TimerPool::new().inbox;
+        let handles: Vec<_> = deadlines
+            .iter()
+            .map(|&deadline| {
+                let (timer, handle) =
+                    inbox.new_timer(deadline, None, TimerTask::Once(Box::new(|_| {})));
+                timers.push(timer);
+                handle
+            })
+            .collect();
+        handles
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % cancel_every != 0)
+            .for_each(|(_, handle)| {
+                handle.cancel();
+            });
+        let mut popped = Vec::new();
+        while let Some(timer) = timers.pop() {
+            (!timer.is_outdated()).then(|| popped.push(timer.deadline));
+        }
+        popped
+    }
+
+    #[test]
+    fn wheel() {
+        let start = Instant::now();
+        // within a tick, across the levels, and beyond the levels.
+        let mut all = deadlines(start, 10_000, Duration::from_secs(3600 * 24 * 365 * 10));
+        all.extend(deadlines(start, 1000, Duration::from_micros(500)));
+        all.extend([start - Duration::from_secs(1), start, start]);
+        let mut sorted = all.clone();
+        sorted.sort();
+        assert_eq!(
+            drain(Timers::Wheel(Box::new(Wheel::new(start))), &all, 1),
+            sorted
+        );
+        // the same timers as the heap, with most of them cancelled.
+        assert_eq!(
+            drain(Timers::Wheel(Box::new(Wheel::new(start))), &all, 10),
+            drain(Timers::Heap(Heap::new()), &all, 10)
+        );
+
+        // push while popping.
+        let mut wheel = Wheel::new(start);
+        let inbox = TimerPool::new().inbox;
+        let new_timer = |deadline| {
+            let task = TimerTask::Once(Box::new(|_| {}));
+            inbox.new_timer(deadline, None, task).0
+        };
+        let mut popped = Vec::new();
+        for chunk in sorted.chunks(100) {
+            chunk
+                .iter()
+                .rev()
+                .for_each(|&deadline| wheel.push(new_timer(deadline)));
+            let timer = wheel.pop().expect("non-empty");
+            // later than the popped one, earlier than the others.
+            let deadline = timer.deadline + Duration::from_micros(1);
+            popped.push(timer.deadline);
+            wheel.push(new_timer(deadline));
+            assert_eq!(wheel.peek().map(|t| t.deadline), Some(deadline));
+        }
+        assert_eq!(wheel.len(), sorted.len());
+        while let Some(timer) = wheel.pop() {
+            popped.push(timer.deadline);
+        }
+        assert!(popped.is_sorted());
+        assert!(wheel.is_empty());
+    }
+
+    /// benchmark of the heap and the wheel, inserting, popping, and popping with 90% of the
+    /// timers cancelled, run by `cargo test --release -- --ignored --nocapture bench`.
+    #[test]
+    #[ignore]
+    fn bench() {
+        let n = 200_000;
+        let start = Instant::now();
+        let deadlines = deadlines(start, n, Duration::from_secs(30));
+        let inbox = TimerPool::new().inbox;
+        let new_timer = |deadline| {
+            let task = TimerTask::Once(Box::new(|_| {}));
+            inbox.new_timer(deadline, None, task).0
+        };
+        let backends = || {
+            [
+                ("heap", Timers::Heap(Heap::new())),
+                ("wheel", Timers::Wheel(Box::new(Wheel::new(start)))),
+            ]
+        };
+        let mut results = Vec::new();
+        for ((name, mut timers), (_, cancelled)) in backends().into_iter().zip(backends()) {
+            let new_timers: Vec<_> = deadlines.iter().map(|&d| new_timer(d)).collect();
+            let begin = Instant::now();
+            new_timers.into_iter().for_each(|timer| timers.push(timer));
+            let insert = begin.elapsed();
+            let begin = Instant::now();
+            let popped = std::iter::from_fn(|| timers.pop()).count();
+            let pop = begin.elapsed();
+            assert_eq!(popped, n);
+
+            let begin = Instant::now();
+            results.push(drain(cancelled, &deadlines, 10));
+            let cancel = begin.elapsed();
+            println!(
+                "{name}: {n} timers, insert {insert:?}, pop {pop:?}, 90% cancelled {cancel:?}"
+            );
+        }
+        assert_eq!(results[0].len(), n / 10);
+        assert_eq!(results[0], results[1]);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::BinaryHeap as Heap,
+    time::{Duration, Instant},
+};
+
+use super::Timer;
+
+const LEVEL_BITS: usize = 6;
+const NUM_SLOTS: usize = 1 << LEVEL_BITS;
+const NUM_LEVELS: usize = 6;
+const SLOT_MASK: u64 = NUM_SLOTS as u64 - 1;
+
+/// resolution of the wheel, the deadlines are still exact.
+const TICK: Duration = Duration::from_millis(1);
+
+struct Level {
+    /// bit `i` is set if `slots[i]` is not empty.
+    occupied: u64,
+    slots: [Vec<Timer>; NUM_SLOTS],
+}
+
+impl Level {
+    fn new() -> Self {
+        Self {
+            occupied: 0,
+            slots: std::array::from_fn(|_| Vec::new()),
+        }
+    }
+}
+
+/// hierarchical timing wheel, 64 slots per level, each level is 64 times coarser than the lower
+/// one.
+///
+/// a timer is put into a level by the highest bit its tick differs from `elapsed`, so the
+/// earliest timer is always in the first occupied slot of the lowest occupied level. the
+/// slots of the higher levels are cascaded to the lower ones as the wheel advances.
+///
+/// insert is O(1), pop is O(1) amortized plus a scan of the earliest slot.
+pub(super) struct Wheel {
+    start: Instant,
+    /// ticks since `start`, no timer is earlier than this tick.
+    elapsed: u64,
+    levels: Box<[Level; NUM_LEVELS]>,
+    /// timers beyond the range of the levels.
+    overflow: Heap<Timer>,
+    len: usize,
+    /// location of the earliest timer in `levels`: (level, slot, index).
+    next: Option<(usize, usize, usize)>,
+}
+
+impl Wheel {
+    /// deadlines earlier than `start` are treated as `start`.
+    pub(super) fn new(start: Instant) -> Self {
+        Self {
+            start,
+            elapsed: 0,
+            levels: Box::new(std::array::from_fn(|_| Level::new())),
+            overflow: Heap::new(),
+            len: 0,
+            next: None,
+        }
+    }
+
+    #[inline]
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub(super) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(super) fn push(&mut self, timer: Timer) {
+        self.len += 1;
+        let when = self.tick_of(timer.deadline).max(self.elapsed);
+        let Some(level) = self.level_of(when) else {
+            self.overflow.push(timer);
+            return;
+        };
+        let slot = Self::slot_of(when, level);
+        let next_deadline = self.peek_levels().map(|t| t.deadline);
+        let is_next = next_deadline.is_none_or(|d| timer.deadline < d);
+        let level_ref = &mut self.levels[level];
+        level_ref.occupied |= 1 << slot;
+        level_ref.slots[slot].push(timer);
+        if is_next {
+            self.next = Some((level, slot, level_ref.slots[slot].len() - 1));
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.levels.iter_mut().for_each(|level| {
+            level.occupied = 0;
+            level.slots.iter_mut().for_each(Vec::clear);
+        });
+        self.overflow.clear();
+        self.len = 0;
+        self.next = None;
+    }
+
+    #[inline]
+    pub(super) fn peek(&self) -> Option<&Timer> {
+        match (self.peek_levels(), self.overflow.peek()) {
+            (Some(t), Some(o)) => Some(if o.deadline < t.deadline { o } else { t }),
+            (t, o) => t.or(o),
+        }
+    }
+
+    pub(super) fn pop(&mut self) -> Option<Timer> {
+        let (level, slot, index) = match (self.next, self.overflow.peek()) {
+            (None, None) => return None,
+            (Some(next), Some(o)) if o.deadline >= self.timer_at(next).deadline => next,
+            (Some(next), None) => next,
+            _ => {
+                self.len -= 1;
+                return self.overflow.pop();
+            }
+        };
+        if level != 0 {
+            self.cascade(level, slot);
+            return self.pop();
+        }
+        self.len -= 1;
+        self.elapsed = (self.elapsed & !SLOT_MASK) | slot as u64;
+        let level_ref = &mut self.levels[0];
+        let timer = level_ref.slots[slot].swap_remove(index);
+        if level_ref.slots[slot].is_empty() {
+            level_ref.occupied &= !(1 << slot);
+        }
+        self.next = self.find_next();
+        Some(timer)
+    }
+
+    pub(super) fn for_each(&self, mut f: impl FnMut(&Timer)) {
+        self.levels
+            .iter()
+            .flat_map(|level| level.slots.iter().flatten())
+            .chain(self.overflow.iter())
+            .for_each(&mut f);
+    }
+}
+
+impl Wheel {
+    #[inline]
+    fn tick_of(&self, deadline: Instant) -> u64 {
+        let ticks = deadline.saturating_duration_since(self.start).as_nanos() / TICK.as_nanos();
+        ticks.try_into().unwrap_or(u64::MAX)
+    }
+
+    /// returns `None` if `when` is beyond the range of the levels.
+    #[inline]
+    fn level_of(&self, when: u64) -> Option<usize> {
+        let masked = (self.elapsed ^ when) | SLOT_MASK;
+        let significant = (u64::BITS - 1 - masked.leading_zeros()) as usize;
+        let level = significant / LEVEL_BITS;
+        (level < NUM_LEVELS).then_some(level)
+    }
+
+    #[inline]
+    fn slot_of(when: u64, level: usize) -> usize {
+        ((when >> (level * LEVEL_BITS)) & SLOT_MASK) as usize
+    }
+
+    #[inline]
+    fn timer_at(&self, (level, slot, index): (usize, usize, usize)) -> &Timer {
+        &self.levels[level].slots[slot][index]
+    }
+
+    #[inline]
+    fn peek_levels(&self) -> Option<&Timer> {
+        self.next.map(|next| self.timer_at(next))
+    }
+
+    fn find_next(&self) -> Option<(usize, usize, usize)> {
+        let (level, level_ref) = self
+            .levels
+            .iter()
+            .enumerate()
+            .find(|(_, level)| level.occupied != 0)?;
+        let slot = level_ref.occupied.trailing_zeros() as usize;
+        let index = level_ref.slots[slot]
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, t)| t.deadline)
+            .map(|(index, _)| index)?;
+        Some((level, slot, index))
+    }
+
+    /// advances `elapsed` to the beginning of the slot, then moves its timers to the lower
+    /// levels.
+    fn cascade(&mut self, level: usize, slot: usize) {
+        let shift = level * LEVEL_BITS;
+        let upper_mask = !((1 << (shift + LEVEL_BITS)) - 1);
+        self.elapsed = (self.elapsed & upper_mask) | ((slot as u64) << shift);
+        let level_ref = &mut self.levels[level];
+        level_ref.occupied &= !(1 << slot);
+        let timers = std::mem::take(&mut level_ref.slots[slot]);
+        self.next = None;
+        self.len -= timers.len();
+        timers.into_iter().for_each(|timer| self.push(timer));
+    }
+}
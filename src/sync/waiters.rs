@@ -0,0 +1,130 @@
+use std::{
+    sync::atomic::{self, AtomicUsize},
+    task::Waker,
+    time::Instant,
+};
+
+use parking_lot::{Condvar, Mutex};
+
+/// wakers of the receivers waiting for a channel.
+///
+/// a waiter must [`register`](Self::register) before checking the channel again, and a
+/// notifier must update the channel before calling [`wake_all`](Self::wake_all), then no wake up
+/// is lost.
+pub(crate) struct WakerSet {
+    len: AtomicUsize,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl WakerSet {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            len: AtomicUsize::new(0),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn register(&self, waker: &Waker) {
+        {
+            let mut wakers = self.wakers.lock();
+            if !wakers.iter().any(|w| w.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+            self.len.store(wakers.len(), atomic::Ordering::Relaxed);
+        }
+        atomic::fence(atomic::Ordering::SeqCst);
+    }
+
+    pub(crate) fn wake_all(&self) {
+        atomic::fence(atomic::Ordering::SeqCst);
+        if self.len.load(atomic::Ordering::Relaxed) == 0 {
+            return;
+        }
+        let wakers = {
+            let mut wakers = self.wakers.lock();
+            self.len.store(0, atomic::Ordering::Relaxed);
+            core::mem::take(&mut *wakers)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// threads blocked on a channel.
+///
+/// like [`WakerSet`], a notifier must update the channel before notifying, and the notifiers
+/// only touch the lock if some thread is sleeping.
+pub(crate) struct Sleepers {
+    len: AtomicUsize,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+impl Sleepers {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            len: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// blocking until `ready` returns `Some`, returns `None` if `deadline` is reached.
+    pub(crate) fn wait_until<R>(
+        &self,
+        deadline: Option<Instant>,
+        mut ready: impl FnMut() -> Option<R>,
+    ) -> Option<R> {
+        loop {
+            if let Some(r) = ready() {
+                return Some(r);
+            }
+            let mut guard = self.lock.lock();
+            self.len.fetch_add(1, atomic::Ordering::Relaxed);
+            atomic::fence(atomic::Ordering::SeqCst);
+            // a notifier must take the lock after this check, so no notification is lost.
+            let r = ready();
+            let timed_out = r.is_none()
+                && match deadline {
+                    Some(deadline) => self.condvar.wait_until(&mut guard, deadline).timed_out(),
+                    None => {
+                        self.condvar.wait(&mut guard);
+                        false
+                    }
+                };
+            self.len.fetch_sub(1, atomic::Ordering::Relaxed);
+            drop(guard);
+            if r.is_some() {
+                return r;
+            }
+            if timed_out {
+                return ready();
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn notify_one(&self) {
+        if self.is_sleeping() {
+            let _guard = self.lock.lock();
+            self.condvar.notify_one();
+        }
+    }
+
+    #[inline]
+    pub(crate) fn notify_all(&self) {
+        if self.is_sleeping() {
+            let _guard = self.lock.lock();
+            self.condvar.notify_all();
+        }
+    }
+}
+
+impl Sleepers {
+    #[inline]
+    fn is_sleeping(&self) -> bool {
+        atomic::fence(atomic::Ordering::SeqCst);
+        self.len.load(atomic::Ordering::Relaxed) != 0
+    }
+}
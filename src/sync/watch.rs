@@ -0,0 +1,369 @@
+use std::{
+    ops::Deref,
+    sync::{
+        Arc,
+        atomic::{self, AtomicBool, AtomicU64, AtomicUsize},
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{RwLock, RwLockReadGuard};
+
+use super::waiters::{Sleepers, WakerSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvError {
+    #[error("Watch channel is disconnected")]
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum RecvTimeoutError {
+    #[error("Watch channel is disconnected")]
+    Disconnected,
+    #[error("Watch channel receiving timeout")]
+    Timeout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum SendError<T> {
+    #[error("Watch channel is disconnected")]
+    Disconnected(T),
+}
+
+struct Shared<T> {
+    value: RwLock<T>,
+    /// bumped while `value` is write locked.
+    version: AtomicU64,
+    /// set when the sender is dropped.
+    closed: AtomicBool,
+    num_receivers: AtomicUsize,
+    sleepers: Sleepers,
+    wakers: WakerSet,
+}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn notify(&self) {
+        self.sleepers.notify_all();
+        self.wakers.wake_all();
+    }
+
+    /// updates `seen` if changed, returns `None` if not changed and not disconnected.
+    fn poll_changed(&self, seen: &mut u64) -> Option<Result<(), RecvTimeoutError>> {
+        // `closed` is loaded first, a value sent before the sender is dropped is still seen.
+        let closed = self.closed.load(atomic::Ordering::Acquire);
+        let version = self.version.load(atomic::Ordering::Acquire);
+        if version != *seen {
+            *seen = version;
+            return Some(Ok(()));
+        }
+        closed.then_some(Err(RecvTimeoutError::Disconnected))
+    }
+}
+
+/// a read lock of the value, don't hold it long, the sender is blocked meanwhile.
+pub struct WatchRef<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+    version: u64,
+}
+
+impl<T> WatchRef<'_, T> {
+    /// the version of the borrowed value.
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl<T> Deref for WatchRef<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+/// observes the newest value, the values in between may be skipped.
+pub struct WatchReceiver<T> {
+    shared: Arc<Shared<T>>,
+    /// version of the last seen value.
+    seen: u64,
+}
+
+impl<T> WatchReceiver<T> {
+    /// the newest value, which is not marked as seen.
+    #[inline]
+    pub fn borrow(&self) -> WatchRef<'_, T> {
+        borrow(&self.shared)
+    }
+
+    /// the newest value, which is marked as seen.
+    #[inline]
+    pub fn borrow_and_update(&mut self) -> WatchRef<'_, T> {
+        let r = borrow(&self.shared);
+        self.seen = r.version;
+        r
+    }
+
+    /// `true` if the newest value is not seen, see [`changed`](Self::changed).
+    #[inline]
+    pub fn has_changed(&self) -> bool {
+        self.shared.version.load(atomic::Ordering::Acquire) != self.seen
+    }
+
+    /// blocking until a value not seen is sent, then marks it as seen.
+    ///
+    /// returns `Disconnected` if the sender is dropped and no value is unseen.
+    pub fn changed(&mut self) -> Result<(), RecvError> {
+        let Self { shared, seen } = self;
+        shared
+            .sleepers
+            .wait_until(None, || shared.poll_changed(seen))
+            .expect("unreachable")
+            .map_err(|_| RecvError::Disconnected)
+    }
+
+    #[inline]
+    pub fn changed_timeout(&mut self, timeout: Duration) -> Result<(), RecvTimeoutError> {
+        self.changed_deadline(Instant::now() + timeout)
+    }
+
+    pub fn changed_deadline(&mut self, deadline: Instant) -> Result<(), RecvTimeoutError> {
+        let Self { shared, seen } = self;
+        shared
+            .sleepers
+            .wait_until(Some(deadline), || shared.poll_changed(seen))
+            .unwrap_or(Err(RecvTimeoutError::Timeout))
+    }
+
+    /// see [`changed`](Self::changed), without blocking the thread.
+    pub async fn changed_async(&mut self) -> Result<(), RecvError> {
+        let Self { shared, seen } = self;
+        let mut changed = || {
+            shared
+                .poll_changed(seen)
+                .map(|r| r.map_err(|_| RecvError::Disconnected))
+        };
+        std::future::poll_fn(|cx| {
+            if let Some(r) = changed() {
+                return Poll::Ready(r);
+            }
+            shared.wakers.register(cx.waker());
+            changed().map_or(Poll::Pending, Poll::Ready)
+        })
+        .await
+    }
+
+    /// `true` if the sender is dropped.
+    #[inline]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.closed.load(atomic::Ordering::Acquire)
+    }
+}
+
+impl<T> Clone for WatchReceiver<T> {
+    /// the clone has seen the same version.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared
+            .num_receivers
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        Self {
+            shared: self.shared.clone(),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> Drop for WatchReceiver<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.shared
+            .num_receivers
+            .fetch_sub(1, atomic::Ordering::Release);
+    }
+}
+
+pub struct WatchSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> WatchSender<T> {
+    /// returns `Disconnected` if all receivers are dropped, then the value is not stored.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.receiver_count() == 0 {
+            return Err(SendError::Disconnected(value));
+        }
+        self.send_replace(value);
+        Ok(())
+    }
+
+    /// stores `value` even if all receivers are dropped, returns the old value.
+    #[inline]
+    pub fn send_replace(&self, value: T) -> T {
+        let mut old = None;
+        self.send_modify(|v| old = Some(core::mem::replace(v, value)));
+        unsafe { old.unwrap_unchecked() }
+    }
+
+    /// modifies the value in place and notifies the receivers.
+    pub fn send_modify(&self, f: impl FnOnce(&mut T)) {
+        {
+            let mut value = self.shared.value.write();
+            f(&mut value);
+            self.shared.version.fetch_add(1, atomic::Ordering::Release);
+        }
+        self.shared.notify();
+    }
+
+    /// like [`send_modify`](Self::send_modify), but the receivers are notified only if `f`
+    /// returns `true`.
+    pub fn send_if_modified(&self, f: impl FnOnce(&mut T) -> bool) -> bool {
+        let modified = {
+            let mut value = self.shared.value.write();
+            let modified = f(&mut value);
+            modified.then(|| self.shared.version.fetch_add(1, atomic::Ordering::Release));
+            modified
+        };
+        modified.then(|| self.shared.notify());
+        modified
+    }
+
+    #[inline]
+    pub fn borrow(&self) -> WatchRef<'_, T> {
+        borrow(&self.shared)
+    }
+
+    /// a new receiver, which has seen the current value.
+    pub fn subscribe(&self) -> WatchReceiver<T> {
+        self.shared
+            .num_receivers
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        let seen = self.shared.version.load(atomic::Ordering::Acquire);
+        WatchReceiver {
+            shared: self.shared.clone(),
+            seen,
+        }
+    }
+
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.num_receivers.load(atomic::Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for WatchSender<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, atomic::Ordering::Release);
+        self.shared.notify();
+    }
+}
+
+#[inline]
+fn borrow<T>(shared: &Shared<T>) -> WatchRef<'_, T> {
+    let guard = shared.value.read();
+    let version = shared.version.load(atomic::Ordering::Acquire);
+    WatchRef { guard, version }
+}
+
+/// a channel of the newest value, initialized with `init`.
+///
+/// the receivers have seen `init`, so [`WatchReceiver::changed`] waits for the next value.
+pub fn watch<T: Send + Sync>(init: T) -> (WatchSender<T>, WatchReceiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(init),
+        version: AtomicU64::new(0),
+        closed: AtomicBool::new(false),
+        num_receivers: AtomicUsize::new(1),
+        sleepers: Sleepers::new(),
+        wakers: WakerSet::new(),
+    });
+    let sender = WatchSender {
+        shared: shared.clone(),
+    };
+    let receiver = WatchReceiver { shared, seen: 0 };
+    (sender, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let (sender, mut receiver) = watch((0_u32, 0_u32));
+        assert!(!receiver.has_changed());
+        assert_eq!(
+            receiver.changed_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..=100 {
+                    sender.send_modify(|(a, b)| (*a, *b) = (i, i * 2));
+                }
+            });
+            // only the newest value matters, but it is always consistent.
+            let mut last = 0;
+            while last != 100 {
+                receiver.changed().expect("disconnected");
+                let value = receiver.borrow_and_update();
+                assert!(value.0 > last && value.1 == value.0 * 2);
+                last = value.0;
+            }
+        });
+        assert_eq!(sender.send_replace((7, 7)), (100, 200));
+        assert!(!sender.send_if_modified(|_| false));
+        let mut clone = receiver.clone();
+        drop(sender);
+        // the value sent before the disconnection is still seen.
+        assert_eq!(receiver.changed(), Ok(()));
+        assert_eq!(*receiver.borrow(), (7, 7));
+        assert_eq!(receiver.changed(), Err(RecvError::Disconnected));
+        assert_eq!(clone.borrow_and_update().version(), 101);
+        assert_eq!(clone.changed(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn subscribe() {
+        let (sender, receiver) = watch(0);
+        drop(receiver);
+        assert_eq!(sender.send(1), Err(SendError::Disconnected(1)));
+        sender.send_replace(2);
+        let receiver = sender.subscribe();
+        assert!(!receiver.has_changed());
+        assert_eq!(*receiver.borrow(), 2);
+        assert_eq!(sender.send(3), Ok(()));
+        assert!(receiver.has_changed());
+    }
+
+    #[test]
+    #[cfg(feature = "thread_async")]
+    fn changed_async() {
+        let (sender, receiver) = watch(0_usize);
+        let thread_pool = crate::thread::AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let mut receiver = receiver.clone();
+                thread_pool.spawn(async move {
+                    let mut last = 0;
+                    while receiver.changed_async().await.is_ok() {
+                        last = *receiver.borrow_and_update();
+                    }
+                    last
+                })
+            })
+            .collect();
+        for i in 1..=10 {
+            sender.send(i).expect("disconnected");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        drop(sender);
+        for h in handles {
+            assert_eq!(h.join().ok(), Some(10));
+        }
+    }
+}
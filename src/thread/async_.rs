@@ -1,34 +1,362 @@
 use std::{
     cell::Cell,
-    collections::LinkedList,
+    collections::VecDeque,
     num::NonZero,
+    panic::AssertUnwindSafe,
     pin::Pin,
-    task::{Poll, Waker},
-    thread::JoinHandle,
+    sync::{
+        Arc, OnceLock,
+        atomic::{self, AtomicBool, AtomicU8, AtomicU64, AtomicUsize},
+    },
+    task::{Context, Poll, Wake, Waker},
 };
 
-use crate::sync::mpmc::queue::{UnboundedReceiver as MpmcReceiver, UnboundedSender as MpmcSender};
+use crossbeam_queue::SegQueue;
+use parking_lot::Mutex;
+
 use crate::sync::spsc::{self, OnceReceiver, OnceSender};
 
-enum Task {
-    Task(Pin<Box<dyn Future<Output = ()> + Send>>),
-    Exit,
+use super::{
+    PanicPayload,
+    join_handle::{self, JoinHandle},
+};
+
+type BoxedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// a task owns its future and acts as its own [`Waker`], waking it schedules only this task.
+struct RawTask {
+    future: Mutex<Option<BoxedFuture>>,
+    state: AtomicU8,
+    scheduler: Arc<Scheduler>,
+}
+
+impl RawTask {
+    /// neither queued nor running.
+    const IDLE: u8 = 0;
+    /// in a run queue.
+    const SCHEDULED: u8 = 1;
+    const RUNNING: u8 = 2;
+    /// woken up while running, should be scheduled again after polling.
+    const NOTIFIED: u8 = 3;
+    const COMPLETE: u8 = 4;
+
+    fn new(future: BoxedFuture, scheduler: Arc<Scheduler>) -> Arc<Self> {
+        scheduler.num_tasks.fetch_add(1, atomic::Ordering::Relaxed);
+        Arc::new(Self {
+            future: Mutex::new(Some(future)),
+            state: AtomicU8::new(Self::SCHEDULED),
+            scheduler,
+        })
+    }
+
+    /// returns `true` if this task should be pushed into a run queue.
+    fn notify(&self) -> bool {
+        let mut state = self.state.load(atomic::Ordering::Acquire);
+        loop {
+            let new_state = match state {
+                Self::IDLE => Self::SCHEDULED,
+                Self::RUNNING => Self::NOTIFIED,
+                _ => return false,
+            };
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                atomic::Ordering::AcqRel,
+                atomic::Ordering::Acquire,
+            ) {
+                Ok(_) => return new_state == Self::SCHEDULED,
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// only called by the worker that popped this task from a run queue.
+    ///
+    /// a panicking task is complete, the first panic is returned by `join` of the pool.
+    fn run(self: Arc<Self>) {
+        self.state.store(Self::RUNNING, atomic::Ordering::Release);
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = self.future.lock();
+        let Some(f) = future.as_mut() else {
+            return;
+        };
+        let poll = std::panic::catch_unwind(AssertUnwindSafe(|| f.as_mut().poll(&mut cx)));
+        if !matches!(poll, Ok(Poll::Pending)) {
+            *future = None;
+            if let Err(payload) = poll {
+                self.scheduler.panic.lock().get_or_insert(payload);
+            }
+            self.state.store(Self::COMPLETE, atomic::Ordering::Release);
+            self.scheduler.finish_task();
+            return;
+        }
+        drop(future);
+        if self
+            .state
+            .compare_exchange(
+                Self::RUNNING,
+                Self::IDLE,
+                atomic::Ordering::AcqRel,
+                atomic::Ordering::Acquire,
+            )
+            .is_err()
+        {
+            // `NOTIFIED`, put it back to the end of the queue.
+            self.state.store(Self::SCHEDULED, atomic::Ordering::Release);
+            let scheduler = self.scheduler.clone();
+            scheduler.schedule(self);
+        }
+    }
+}
+
+impl Wake for RawTask {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        if self.notify() {
+            let scheduler = self.scheduler.clone();
+            scheduler.schedule(self);
+        }
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        if self.notify() {
+            self.scheduler.schedule(self.clone());
+        }
+    }
+}
+
+thread_local! {
+    /// the scheduler and the index of the worker running on this thread.
+    static CURRENT_WORKER: Cell<(*const Scheduler, usize)> =
+        const { Cell::new((core::ptr::null(), 0)) };
+}
+
+/// statistics of a worker of [`AsyncThreadPool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WorkerStats {
+    /// number of tasks in the local run queue.
+    pub queue_len: usize,
+    /// number of tasks stolen from other workers.
+    pub steals: u64,
+    /// number of tasks polled.
+    pub polls: u64,
+}
+
+/// statistics of [`AsyncThreadPool`], see [`AsyncThreadPool::stats`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PoolStats {
+    /// number of tasks waiting in the shared queue.
+    pub injector_len: usize,
+    pub workers: Box<[WorkerStats]>,
+}
+
+struct Worker {
+    /// local run queue, the owner pops from the front, others steal from the back.
+    tasks: Mutex<VecDeque<Arc<RawTask>>>,
+    /// wakes the worker thread.
+    waker: OnceLock<Waker>,
+    sleeping: AtomicBool,
+    steals: AtomicU64,
+    polls: AtomicU64,
+}
+
+impl Worker {
+    #[inline]
+    fn wake(&self) {
+        self.waker.get().map(Waker::wake_by_ref);
+    }
 }
 
-struct RawAsyncThread(JoinHandle<()>);
+/// run queues of a [`RawAsyncThread`] group.
+///
+/// a task woken on a worker thread goes to the local queue of that worker, otherwise to the
+/// shared injector. idle workers steal tasks from the others, so ready tasks migrate to them.
+struct Scheduler {
+    injector: SegQueue<Arc<RawTask>>,
+    workers: Box<[Worker]>,
+    /// number of unfinished tasks.
+    num_tasks: AtomicUsize,
+    need_exit: AtomicBool,
+    /// the first panic of the tasks.
+    panic: Mutex<Option<PanicPayload>>,
+}
+
+impl Scheduler {
+    /// check the injector first every this many ticks, so it won't be starved by local tasks.
+    const INJECTOR_INTERVAL: u32 = 61;
+    /// poll at most this many tasks before checking the exit condition.
+    const BUDGET: u32 = 128;
+
+    fn new(num_workers: usize) -> Arc<Self> {
+        let workers = (0..num_workers)
+            .map(|_| Worker {
+                tasks: Mutex::new(VecDeque::new()),
+                waker: OnceLock::new(),
+                sleeping: AtomicBool::new(false),
+                steals: AtomicU64::new(0),
+                polls: AtomicU64::new(0),
+            })
+            .collect();
+        Arc::new(Self {
+            injector: SegQueue::new(),
+            workers,
+            num_tasks: AtomicUsize::new(0),
+            need_exit: AtomicBool::new(false),
+            panic: Mutex::new(None),
+        })
+    }
+
+    #[inline]
+    fn worker(&self, index: usize) -> &Worker {
+        unsafe { self.workers.get_unchecked(index) }
+    }
+
+    /// returns the index of the worker running on this thread.
+    #[inline]
+    fn current_worker(&self) -> Option<usize> {
+        let (scheduler, index) = CURRENT_WORKER.get();
+        core::ptr::eq(scheduler, self).then_some(index)
+    }
+
+    fn schedule(&self, task: Arc<RawTask>) {
+        if let Some(index) = self.current_worker() {
+            let has_more = {
+                let mut tasks = self.worker(index).tasks.lock();
+                tasks.push_back(task);
+                tasks.len() > 1
+            };
+            // the current worker is busy, let an idle one steal.
+            has_more.then(|| self.wake_sleeping());
+        } else {
+            self.injector.push(task);
+            self.wake_sleeping();
+        }
+    }
+
+    /// schedules without waking any worker, call [`wake_sleeping`](Self::wake_sleeping) later.
+    #[inline]
+    fn inject(&self, task: Arc<RawTask>) {
+        self.injector.push(task);
+    }
+
+    /// wakes one sleeping worker, if any.
+    fn wake_sleeping(&self) {
+        atomic::fence(atomic::Ordering::SeqCst);
+        self.workers
+            .iter()
+            .find(|w| {
+                w.sleeping.load(atomic::Ordering::Relaxed)
+                    && w.sleeping.swap(false, atomic::Ordering::AcqRel)
+            })
+            .map(Worker::wake);
+    }
+
+    fn wake_all(&self) {
+        self.workers.iter().for_each(Worker::wake);
+    }
+
+    #[inline]
+    fn finish_task(&self) {
+        let num_tasks = self.num_tasks.fetch_sub(1, atomic::Ordering::AcqRel);
+        if num_tasks == 1 && self.need_exit.load(atomic::Ordering::Acquire) {
+            self.wake_all();
+        }
+    }
+
+    /// the workers exit after all tasks are finished.
+    fn exit(&self) {
+        self.need_exit.store(true, atomic::Ordering::Release);
+        self.wake_all();
+    }
+
+    /// returns the result of the workers, or the first panic of the tasks.
+    fn join_result(&self, result: std::thread::Result<()>) -> std::thread::Result<()> {
+        let panic = self.panic.lock().take();
+        result.and(panic.map_or(Ok(()), Err))
+    }
+
+    #[inline]
+    fn can_exit(&self) -> bool {
+        self.need_exit.load(atomic::Ordering::Acquire)
+            && self.num_tasks.load(atomic::Ordering::Acquire) == 0
+    }
+
+    fn next_task(&self, index: usize, tick: u32) -> Option<Arc<RawTask>> {
+        if tick.is_multiple_of(Self::INJECTOR_INTERVAL)
+            && let Some(task) = self.injector.pop()
+        {
+            return Some(task);
+        }
+        let local = self.worker(index).tasks.lock().pop_front();
+        local
+            .or_else(|| self.injector.pop())
+            .or_else(|| self.steal(index))
+    }
+
+    /// steals half of the tasks from the first non-empty worker after `index`.
+    fn steal(&self, index: usize) -> Option<Arc<RawTask>> {
+        let num_workers = self.workers.len();
+        let mut stolen = (1..num_workers)
+            .map(|offset| self.worker((index + offset) % num_workers))
+            .find_map(|victim| {
+                let mut tasks = victim.tasks.lock();
+                let len = tasks.len();
+                (len != 0).then(|| tasks.split_off(len / 2))
+            })?;
+        let worker = self.worker(index);
+        worker
+            .steals
+            .fetch_add(stolen.len() as u64, atomic::Ordering::Relaxed);
+        let task = stolen.pop_front();
+        worker.tasks.lock().append(&mut stolen);
+        task
+    }
+
+    fn has_task(&self, index: usize) -> bool {
+        !self.injector.is_empty()
+            || self
+                .workers
+                .iter()
+                .enumerate()
+                .any(|(i, w)| i != index && !w.tasks.lock().is_empty())
+            || !self.worker(index).tasks.lock().is_empty()
+    }
+
+    fn stats(&self) -> PoolStats {
+        let workers = self
+            .workers
+            .iter()
+            .map(|w| WorkerStats {
+                queue_len: w.tasks.lock().len(),
+                steals: w.steals.load(atomic::Ordering::Relaxed),
+                polls: w.polls.load(atomic::Ordering::Relaxed),
+            })
+            .collect();
+        PoolStats {
+            injector_len: self.injector.len(),
+            workers,
+        }
+    }
+}
+
+struct RawAsyncThread(std::thread::JoinHandle<()>);
 
 impl RawAsyncThread {
     fn new(
-        task_receiver: MpmcReceiver<Task>,
+        scheduler: Arc<Scheduler>,
+        index: usize,
         builder: std::thread::Builder,
-    ) -> std::io::Result<(Self, Waker)> {
-        let (waker_sender, waker_receiver) = spsc::once();
-        let join_handle = builder.spawn(move || Self::thread_main(task_receiver, waker_sender))?;
-        let waker = waker_receiver.recv();
-        Ok((Self(join_handle), waker))
+    ) -> std::io::Result<Self> {
+        let (ready_sender, ready_receiver) = spsc::once();
+        let join_handle =
+            builder.spawn(move || Self::thread_main(scheduler, index, ready_sender))?;
+        ready_receiver.recv();
+        Ok(Self(join_handle))
     }
 
-    /// before [`join`](Self::join), ensure that this worker can receive a [`Task::Exit`].
+    /// before [`join`](Self::join), ensure that [`Scheduler::exit`] is called.
     ///
     /// othewise `join` will block forever...
     fn join(self) -> std::thread::Result<()> {
@@ -38,53 +366,77 @@ impl RawAsyncThread {
 
 impl RawAsyncThread {
     #[inline]
-    fn thread_main(task_receiver: MpmcReceiver<Task>, waker_sender: OnceSender<Waker>) {
-        let mut tasks = LinkedList::new();
-        let mut need_exit = false;
-        let mut waker_sender = Some(waker_sender);
+    fn thread_main(scheduler: Arc<Scheduler>, index: usize, ready_sender: OnceSender<()>) {
+        CURRENT_WORKER.set((Arc::as_ptr(&scheduler), index));
+        let mut ready_sender = Some(ready_sender);
+        let mut tick = 0_u32;
+        let worker = scheduler.worker(index);
 
         let f = std::future::poll_fn(|cx| {
-            waker_sender.take().map(|s| s.send(cx.waker().clone()));
-
-            // step 1: receive all tasks if don't need exit.
-            if !need_exit {
-                // when there are multiple receivers on the same channel, there may be multiple
-                // `Task::Exit` in the channel.
-                let _ = task_receiver.try_iter().try_for_each(|task| match task {
-                    Task::Task(task) => {
-                        tasks.push_back(task);
-                        Ok(())
-                    }
-                    Task::Exit => {
-                        need_exit = true;
-                        Err(())
-                    }
-                });
+            ready_sender.take().map(|s| {
+                let _ = worker.waker.set(cx.waker().clone());
+                s.send(());
+            });
+            worker.sleeping.store(false, atomic::Ordering::Relaxed);
+
+            for _ in 0..Scheduler::BUDGET {
+                tick = tick.wrapping_add(1);
+                let Some(task) = scheduler.next_task(index, tick) else {
+                    break;
+                };
+                worker.polls.fetch_add(1, atomic::Ordering::Relaxed);
+                task.run();
             }
 
-            // step 2: poll futures, retain pending ones.
-            tasks
-                .extract_if(|f| f.as_mut().poll(cx).is_ready())
-                .for_each(drop);
-
-            if need_exit && tasks.is_empty() {
+            if scheduler.can_exit() {
                 // ready means exit.
-                Poll::Ready(())
-            } else {
-                // sender should send a task and wake this thread up.
-                Poll::Pending
+                return Poll::Ready(());
+            }
+            if scheduler.has_task(index) {
+                // out of budget.
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            // go to sleep, then check again in case a task is scheduled in the meantime.
+            worker.sleeping.store(true, atomic::Ordering::Relaxed);
+            atomic::fence(atomic::Ordering::SeqCst);
+            if scheduler.has_task(index) || scheduler.can_exit() {
+                worker.sleeping.store(false, atomic::Ordering::Relaxed);
+                cx.waker().wake_by_ref();
             }
+            // a scheduled task or `Scheduler::exit` should wake this thread up.
+            Poll::Pending
         });
 
         crate::async_::block_on(f);
     }
 }
 
+/// spawns `num_workers` workers sharing a new [`Scheduler`].
+///
+/// if any worker fails to spawn, the spawned ones exit.
+fn spawn_workers(
+    num_workers: usize,
+    mut builder: impl FnMut(usize) -> std::thread::Builder,
+) -> std::io::Result<(Box<[RawAsyncThread]>, Arc<Scheduler>)> {
+    let scheduler = Scheduler::new(num_workers);
+    let mut workers = Vec::with_capacity(num_workers);
+    for index in 0..num_workers {
+        match RawAsyncThread::new(scheduler.clone(), index, builder(index)) {
+            Ok(worker) => workers.push(worker),
+            Err(err) => {
+                scheduler.exit();
+                return Err(err);
+            }
+        }
+    }
+    Ok((workers.into_boxed_slice(), scheduler))
+}
+
 /// similar to a thread pool, [`AsyncThread`] can execute async tasks on an independent thread.
 pub struct AsyncThread {
     raw: Option<RawAsyncThread>,
-    task_sender: MpmcSender<Task>,
-    waker: Waker,
+    scheduler: Arc<Scheduler>,
 }
 
 impl Default for AsyncThread {
@@ -100,13 +452,11 @@ impl AsyncThread {
     }
 
     pub fn with_builder(builder: std::thread::Builder) -> std::io::Result<Self> {
-        let (task_sender, task_receiver) = crate::sync::mpmc::queue::unbounded();
-        let (raw, waker) = RawAsyncThread::new(task_receiver, builder)?;
-        Ok(Self {
-            raw: Some(raw),
-            task_sender,
-            waker,
-        })
+        let mut builder = Some(builder);
+        let (workers, scheduler) =
+            spawn_workers(1, |_| unsafe { builder.take().unwrap_unchecked() })?;
+        let raw = workers.into_iter().next();
+        Ok(Self { raw, scheduler })
     }
 
     #[inline]
@@ -126,7 +476,21 @@ impl AsyncThread {
 
     #[inline]
     pub fn add_task_boxed(&self, task: Box<dyn Future<Output = ()> + Send>) {
-        self.send_and_wake(Task::Task(Box::into_pin(task)));
+        let task = RawTask::new(Box::into_pin(task), self.scheduler.clone());
+        self.scheduler.schedule(task);
+    }
+
+    /// similar to [`add_task`](Self::add_task), returns a [`JoinHandle`] that can be awaited,
+    /// joined or aborted.
+    ///
+    /// panics of the task are caught and reported by the [`JoinHandle`].
+    pub fn spawn<R: Send + 'static>(
+        &self,
+        task: impl Future<Output = R> + Send + 'static,
+    ) -> JoinHandle<R> {
+        let (task, handle) = join_handle::abortable(task);
+        self.add_task(task);
+        handle
     }
 
     /// this function will wake the thread only once, so it might be slightly more efficient.
@@ -135,44 +499,41 @@ impl AsyncThread {
         task_iter: impl IntoIterator<Item = impl Future<Output = ()> + Send + 'static>,
     ) {
         task_iter.into_iter().for_each(|task| {
-            self.task_sender.send(Task::Task(Box::pin(task)));
+            let task = RawTask::new(Box::pin(task), self.scheduler.clone());
+            self.scheduler.inject(task);
         });
-        self.waker.wake_by_ref();
+        self.scheduler.wake_sleeping();
     }
 
+    /// returns the first panic of the tasks.
     pub fn join(mut self) -> std::thread::Result<()> {
         unsafe { self.join_by_ref().unwrap_unchecked() }
     }
 }
 
 impl AsyncThread {
-    fn send_and_wake(&self, task: Task) {
-        self.task_sender.send(task);
-        self.waker.wake_by_ref();
-    }
-
     fn join_by_ref(&mut self) -> Option<std::thread::Result<()>> {
         self.raw.take().map(|j| {
-            self.send_and_wake(Task::Exit);
-            j.join()
+            self.scheduler.exit();
+            self.scheduler.join_result(j.join())
         })
     }
 }
 
 impl Drop for AsyncThread {
     fn drop(&mut self) {
-        self.join_by_ref().map(|r| r.expect("AsyncThread panic"));
+        self.join_by_ref()
+            .map(|r| (!std::thread::panicking()).then(|| r.expect("AsyncThread panic")));
     }
 }
 
 /// async version of [`ThreadPool`].
 ///
-/// each thread can run async tasks.
+/// each thread can run async tasks. every worker has a local run queue, idle workers steal
+/// ready tasks from the busy ones.
 pub struct AsyncThreadPool {
     workers: Option<Box<[RawAsyncThread]>>,
-    wakers: Box<[Waker]>,
-    task_sender: MpmcSender<Task>,
-    index_to_wake: Cell<usize>,
+    scheduler: Arc<Scheduler>,
 }
 
 impl AsyncThreadPool {
@@ -183,27 +544,12 @@ impl AsyncThreadPool {
 
     pub fn with_builder(
         num_workers: NonZero<usize>,
-        mut builder: impl FnMut(usize) -> std::thread::Builder,
+        builder: impl FnMut(usize) -> std::thread::Builder,
     ) -> std::io::Result<Self> {
-        let num_workers = num_workers.get();
-        let (task_sender, task_receiver) = crate::sync::mpmc::queue::unbounded();
-        let (mut workers, mut wakers) = (
-            Vec::with_capacity(num_workers),
-            Vec::with_capacity(num_workers),
-        );
-        for index in 0..num_workers {
-            let builder = builder(index);
-            let (worker, waker) = RawAsyncThread::new(task_receiver.clone(), builder)?;
-            workers.push(worker);
-            wakers.push(waker);
-        }
-        let workers = workers.into_boxed_slice();
-        let wakers = wakers.into_boxed_slice();
+        let (workers, scheduler) = spawn_workers(num_workers.get(), builder)?;
         Ok(Self {
             workers: Some(workers),
-            wakers,
-            task_sender,
-            index_to_wake: Cell::new(0),
+            scheduler,
         })
     }
 
@@ -225,7 +571,21 @@ impl AsyncThreadPool {
 
     #[inline]
     pub fn add_task_boxed(&self, task: Box<dyn Future<Output = ()> + Send>) {
-        self.send_and_wake(Task::Task(Box::into_pin(task)));
+        let task = RawTask::new(Box::into_pin(task), self.scheduler.clone());
+        self.scheduler.schedule(task);
+    }
+
+    /// similar to [`add_task`](Self::add_task), returns a [`JoinHandle`] that can be awaited,
+    /// joined or aborted.
+    ///
+    /// panics of the task are caught and reported by the [`JoinHandle`].
+    pub fn spawn<R: Send + 'static>(
+        &self,
+        task: impl Future<Output = R> + Send + 'static,
+    ) -> JoinHandle<R> {
+        let (task, handle) = join_handle::abortable(task);
+        self.add_task(task);
+        handle
     }
 
     #[inline]
@@ -233,30 +593,24 @@ impl AsyncThreadPool {
         unsafe { self.workers.as_ref().unwrap_unchecked() }.len()
     }
 
+    /// a snapshot of the run queues and counters of all workers.
+    #[inline]
+    pub fn stats(&self) -> PoolStats {
+        self.scheduler.stats()
+    }
+
+    /// returns the first panic of the tasks.
     pub fn join(mut self) -> std::thread::Result<()> {
         unsafe { self.join_by_ref().unwrap_unchecked() }
     }
 }
 
 impl AsyncThreadPool {
-    fn next_index_to_wake(&self, index_to_wake: usize) -> usize {
-        (index_to_wake + 1) % self.num_workers()
-    }
-
-    fn send_and_wake(&self, task: Task) {
-        self.task_sender.send(task);
-        let index = self.index_to_wake.get();
-        unsafe { self.wakers.get_unchecked(index) }.wake_by_ref();
-        self.index_to_wake.set(self.next_index_to_wake(index));
-    }
-
     fn join_by_ref(&mut self) -> Option<std::thread::Result<()>> {
         self.workers.take().map(|workers| {
-            workers
-                .iter()
-                .for_each(|_| self.task_sender.send(Task::Exit));
-            self.wakers.iter().for_each(|w| w.wake_by_ref());
-            workers.into_iter().try_for_each(|w| w.join())
+            self.scheduler.exit();
+            let result = workers.into_iter().try_for_each(|w| w.join());
+            self.scheduler.join_result(result)
         })
     }
 }
@@ -264,7 +618,7 @@ impl AsyncThreadPool {
 impl Drop for AsyncThreadPool {
     fn drop(&mut self) {
         self.join_by_ref()
-            .map(|r| r.expect("AsyncThreadPool panic"));
+            .map(|r| (!std::thread::panicking()).then(|| r.expect("AsyncThreadPool panic")));
     }
 }
 
@@ -304,4 +658,115 @@ mod tests {
         });
         println!("hello!");
     }
+
+    #[test]
+    fn spawn() {
+        let thread_pool = AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        let handle = thread_pool.spawn(async {
+            async_::sleep(Duration::from_millis(100)).await;
+            42
+        });
+        let r = thread_pool.spawn(async { handle.await.ok() }).join();
+        assert_eq!(r.ok(), Some(Some(42)));
+
+        let pending = thread_pool.spawn(std::future::pending::<()>());
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!pending.is_finished());
+        pending.abort();
+        assert!(pending.join().is_err_and(|e| e.is_cancelled()));
+
+        let worker = AsyncThread::new();
+        let panicked = worker.spawn(async { panic!("spawn panic") });
+        assert!(panicked.join().is_err_and(|e| e.is_panicked()));
+        // the thread survives the panic.
+        assert_eq!(worker.spawn(async { 1 }).join().ok(), Some(1));
+    }
+
+    #[test]
+    fn task_panic() {
+        let thread_pool = AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        thread_pool.add_task(async { panic!("task panic") });
+        thread_pool.add_task(async { panic!("another panic") });
+        // the workers survive the panics.
+        let r = thread_pool.add_task_sync(async { 1 }).recv();
+        assert_eq!(r, 1);
+        let payload = thread_pool.join().expect_err("task panic");
+        let message = payload.downcast_ref::<&str>().copied();
+        assert!(matches!(message, Some("task panic" | "another panic")));
+
+        let thread_pool = AsyncThreadPool::new(2.try_into().expect("non-zero"));
+        thread_pool.add_task(async { panic!("task panic") });
+        std::thread::sleep(Duration::from_millis(100));
+        // dropping the pool doesn't hang.
+        let r = std::panic::catch_unwind(AssertUnwindSafe(|| drop(thread_pool)));
+        assert!(r.is_err());
+    }
+
+    /// benchmark: only the woken tasks are polled, idle tasks cost nothing.
+    #[test]
+    fn bench_idle_tasks() {
+        const NUM_IDLE_TASKS: usize = 10_000;
+        const NUM_YIELDS: usize = 10_000;
+
+        let worker = AsyncThread::new();
+        let polls = Arc::new(AtomicUsize::new(0));
+        let senders: Vec<_> = (0..NUM_IDLE_TASKS)
+            .map(|_| {
+                let (sender, receiver) = spsc::once::<()>();
+                let polls = polls.clone();
+                let mut idle = Box::pin(receiver.recv_async());
+                worker.add_task(std::future::poll_fn(move |cx| {
+                    polls.fetch_add(1, atomic::Ordering::Relaxed);
+                    idle.as_mut().poll(cx)
+                }));
+                sender
+            })
+            .collect();
+
+        let busy = worker.spawn(async {
+            for _ in 0..NUM_YIELDS {
+                async_::yield_now().await;
+            }
+        });
+        assert!(busy.join().is_ok());
+        // the idle tasks are polled only once, however many times the busy one yields.
+        assert_eq!(polls.load(atomic::Ordering::Relaxed), NUM_IDLE_TASKS);
+
+        senders.into_iter().for_each(|s| s.send(()));
+        assert!(worker.join().is_ok());
+        assert_eq!(polls.load(atomic::Ordering::Relaxed), NUM_IDLE_TASKS * 2);
+    }
+
+    #[test]
+    fn work_stealing() {
+        const NUM_TASKS: usize = 64;
+
+        let thread_pool = AsyncThreadPool::new(4.try_into().expect("non-zero"));
+        let thread_ids = Arc::new(Mutex::new(std::collections::HashSet::new()));
+        let senders: Vec<_> = (0..NUM_TASKS)
+            .map(|_| {
+                let (sender, receiver) = spsc::once::<()>();
+                let thread_ids = thread_ids.clone();
+                thread_pool.add_task(async move {
+                    receiver.recv_async().await;
+                    // blocks the worker, the queued tasks should be stolen by the others.
+                    std::thread::sleep(Duration::from_millis(5));
+                    thread_ids.lock().insert(std::thread::current().id());
+                });
+                sender
+            })
+            .collect();
+        std::thread::sleep(Duration::from_millis(100));
+        // all tasks are woken on one worker, so they are pushed to its local queue.
+        thread_pool.add_task(async move { senders.into_iter().for_each(|s| s.send(())) });
+
+        let begin = std::time::Instant::now();
+        while thread_ids.lock().len() < 2 && begin.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let stats = thread_pool.stats();
+        assert!(stats.workers.iter().map(|w| w.steals).sum::<u64>() > 0);
+        assert!(thread_pool.join().is_ok());
+        assert!(thread_ids.lock().len() > 1);
+    }
 }
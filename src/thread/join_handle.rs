@@ -0,0 +1,188 @@
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{self, AtomicBool},
+    },
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use super::PanicPayload;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JoinError {
+    #[error("task was cancelled")]
+    Cancelled,
+    #[error("task panicked")]
+    Panicked(PanicPayload),
+}
+
+impl JoinError {
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+
+    #[inline]
+    pub fn is_panicked(&self) -> bool {
+        matches!(self, Self::Panicked(_))
+    }
+
+    /// returns the panic payload if the task panicked.
+    #[inline]
+    pub fn into_panic(self) -> Option<PanicPayload> {
+        match self {
+            Self::Panicked(payload) => Some(payload),
+            Self::Cancelled => None,
+        }
+    }
+}
+
+struct JoinSlot<T> {
+    result: Option<Result<T, JoinError>>,
+    finished: bool,
+    join_waker: Option<Waker>,
+    task_waker: Option<Waker>,
+}
+
+struct JoinState<T> {
+    slot: Mutex<JoinSlot<T>>,
+    condvar: Condvar,
+    aborted: AtomicBool,
+}
+
+impl<T> JoinState<T> {
+    fn complete(&self, result: Result<T, JoinError>) {
+        let join_waker = {
+            let mut slot = self.slot.lock();
+            if slot.finished {
+                return;
+            }
+            slot.result = Some(result);
+            slot.finished = true;
+            slot.task_waker = None;
+            self.condvar.notify_all();
+            slot.join_waker.take()
+        };
+        join_waker.map(Waker::wake);
+    }
+}
+
+/// handle of a task spawned by [`AsyncThread::spawn`](super::AsyncThread::spawn) or
+/// [`AsyncThreadPool::spawn`](super::AsyncThreadPool::spawn).
+///
+/// it can be awaited by other tasks or [`join`](Self::join)ed by a thread. dropping the handle
+/// detaches the task.
+pub struct JoinHandle<T> {
+    state: Arc<JoinState<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// cancels the task, the future is dropped at its next poll.
+    ///
+    /// no effect if the task is finished.
+    pub fn abort(&self) {
+        self.state.aborted.store(true, atomic::Ordering::Release);
+        let task_waker = self.state.slot.lock().task_waker.take();
+        task_waker.map(Waker::wake);
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.state.slot.lock().finished
+    }
+
+    /// blocks until the task is finished.
+    ///
+    /// never call this inside a task running on the same thread, otherwise it blocks forever.
+    pub fn join(self) -> Result<T, JoinError> {
+        let mut slot = self.state.slot.lock();
+        self.state
+            .condvar
+            .wait_while(&mut slot, |slot| slot.result.is_none());
+        unsafe { slot.result.take().unwrap_unchecked() }
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.state.slot.lock();
+        if let Some(result) = slot.result.take() {
+            return Poll::Ready(result);
+        }
+        assert!(!slot.finished, "`JoinHandle` polled after completion");
+        match &mut slot.join_waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => slot.join_waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+/// completes the [`JoinHandle`] with [`JoinError::Cancelled`] if the task is dropped by the
+/// executor before finishing.
+struct CancelOnDrop<T>(Arc<JoinState<T>>);
+
+impl<T> Drop for CancelOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.complete(Err(JoinError::Cancelled));
+    }
+}
+
+/// wraps `f` into a task which catches panics and can be aborted by the returned
+/// [`JoinHandle`].
+pub(crate) fn abortable<T, F>(f: F) -> (impl Future<Output = ()> + Send + 'static, JoinHandle<T>)
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    let state = Arc::new(JoinState {
+        slot: Mutex::new(JoinSlot {
+            result: None,
+            finished: false,
+            join_waker: None,
+            task_waker: None,
+        }),
+        condvar: Condvar::new(),
+        aborted: AtomicBool::new(false),
+    });
+    let handle = JoinHandle {
+        state: state.clone(),
+    };
+    let task = async move {
+        let guard = CancelOnDrop(state);
+        let state = &*guard.0;
+        let mut f = std::pin::pin!(Some(f));
+        let result = std::future::poll_fn(|cx| {
+            if state.aborted.load(atomic::Ordering::Acquire) {
+                f.set(None);
+                return Poll::Ready(Err(JoinError::Cancelled));
+            }
+            {
+                let mut slot = state.slot.lock();
+                match &mut slot.task_waker {
+                    Some(waker) => waker.clone_from(cx.waker()),
+                    None => slot.task_waker = Some(cx.waker().clone()),
+                }
+            }
+            let fut = unsafe { f.as_mut().as_pin_mut().unwrap_unchecked() };
+            match std::panic::catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+                Ok(Poll::Ready(r)) => Poll::Ready(Ok(r)),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(payload) => {
+                    f.set(None);
+                    Poll::Ready(Err(JoinError::Panicked(payload)))
+                }
+            }
+        })
+        .await;
+        state.complete(result);
+    };
+    (task, handle)
+}
@@ -1,21 +1,39 @@
 #[cfg(feature = "thread_async")]
 pub mod async_;
+#[cfg(feature = "thread_async")]
+pub mod join_handle;
 
+pub mod pacer;
+pub mod par;
+pub mod scope;
+pub mod sleeper;
 pub mod timer;
 pub mod worker;
 
 #[cfg(feature = "thread_async")]
-pub use async_::{AsyncThread, AsyncThreadPool};
+pub use async_::{AsyncThread, AsyncThreadPool, PoolStats, WorkerStats};
+#[cfg(feature = "thread_async")]
+pub use join_handle::{JoinError, JoinHandle};
 
+pub use pacer::{Frame, FramePacer};
+pub use par::ParSource;
+pub use scope::{Scope, ScopedJoinHandle};
+pub use sleeper::{PreciseSleeper, SleepStats};
 pub use timer::TimerThread;
-pub use worker::{ThreadPool, WorkerThread};
+pub use worker::{
+    AddTaskTimeoutError, Elastic, PanicPolicy, Priority, ThreadPool, ThreadPoolConfig,
+    TryAddTaskError, WorkerThread,
+};
 
 use std::time::{Duration, Instant};
 
+/// payload of a caught panic, see [`std::panic::catch_unwind`].
+pub type PanicPayload = Box<dyn std::any::Any + Send + 'static>;
+
+/// sleeps by [`PreciseSleeper::global`], the spin window is calibrated at runtime.
 #[inline(always)]
 pub fn precise_sleep(duration: Duration) {
-    const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
-    precise_sleep_with_spin_threshold(duration, SPIN_THRESHOLD);
+    PreciseSleeper::global().sleep(duration);
 }
 
 #[inline]
@@ -30,10 +48,17 @@ pub fn spin_sleep(duration: Duration) {
     spin_sleep_until(Instant::now() + duration);
 }
 
+/// yields to other threads until close to `deadline`, then busy waits with
+/// [`std::hint::spin_loop`].
 #[inline(always)]
 pub fn spin_sleep_until(deadline: Instant) {
-    while Instant::now() < deadline {
-        std::thread::yield_now();
+    const YIELD_THRESHOLD: Duration = Duration::from_micros(100);
+    loop {
+        match deadline.checked_duration_since(Instant::now()) {
+            None => break,
+            Some(remaining) if remaining > YIELD_THRESHOLD => std::thread::yield_now(),
+            Some(_) => std::hint::spin_loop(),
+        }
     }
 }
 
@@ -0,0 +1,301 @@
+use std::time::{Duration, Instant};
+
+use super::PreciseSleeper;
+
+/// a frame paced by [`FramePacer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    /// the scheduled instant of this frame.
+    pub tick: Instant,
+    /// time since the previous frame.
+    pub delta: Duration,
+    /// number of fixed updates to run in this frame.
+    pub fixed_steps: u32,
+    /// progress to the next fixed update in `0.0..1.0`, for interpolating the rendered state
+    /// between the last two fixed updates.
+    pub alpha: f64,
+    /// number of frames dropped right before this one.
+    pub dropped: u64,
+}
+
+/// paces a game-style loop at a target rate, with fixed-timestep updates.
+///
+/// frames stay aligned to the schedule, the missed ones are dropped and reported.
+///
+/// ``` no_run
+/// # use std::time::Duration;
+/// # use sak_rs::thread::FramePacer;
+/// let mut pacer = FramePacer::new(60.0);
+/// pacer.set_fixed_step(Duration::from_secs(1) / 120);
+/// loop {
+///     let frame = pacer.wait();
+///     (0..frame.fixed_steps).for_each(|_| { /* update */ });
+///     /* render with frame.alpha */
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    period: Duration,
+    fixed_step: Duration,
+    max_fixed_steps: u32,
+    last_tick: Instant,
+    last_frame: Instant,
+    accumulator: Duration,
+    frames: u64,
+    dropped_frames: u64,
+}
+
+impl FramePacer {
+    /// default of [`max_fixed_steps`](Self::max_fixed_steps).
+    pub const MAX_FIXED_STEPS: u32 = 8;
+
+    /// the fixed step defaults to the frame period.
+    ///
+    /// # Panics
+    ///
+    /// panics if `fps` is not positive and finite.
+    #[inline]
+    pub fn new(fps: f64) -> Self {
+        Self::with_period(Self::fps_to_period(fps))
+    }
+
+    /// the fixed step defaults to `period`.
+    ///
+    /// # Panics
+    ///
+    /// panics if `period` is zero.
+    pub fn with_period(period: Duration) -> Self {
+        assert!(!period.is_zero(), "`period` must be non-zero");
+        let instant_now = Instant::now();
+        Self {
+            period,
+            fixed_step: period,
+            max_fixed_steps: Self::MAX_FIXED_STEPS,
+            last_tick: instant_now,
+            last_frame: instant_now,
+            accumulator: Duration::ZERO,
+            frames: 0,
+            dropped_frames: 0,
+        }
+    }
+
+    /// blocks until the next frame by [`PreciseSleeper::global`].
+    pub fn wait(&mut self) -> Frame {
+        let next_tick = self.next_tick();
+        PreciseSleeper::global().sleep_until(next_tick);
+        self.frame(next_tick, Instant::now())
+    }
+
+    /// completes at the next frame, without blocking the executor until the last spin
+    /// window of [`PreciseSleeper::global`].
+    #[cfg(feature = "async")]
+    pub async fn tick(&mut self) -> Frame {
+        let next_tick = self.next_tick();
+        let sleeper = PreciseSleeper::global();
+        let coarse_tick = next_tick.checked_sub(sleeper.spin_threshold());
+        crate::async_::sleep_until(coarse_tick.unwrap_or(next_tick)).await;
+        sleeper.sleep_until(next_tick);
+        self.frame(next_tick, Instant::now())
+    }
+
+    /// the next frame is scheduled one period from now, the fixed updates are discarded.
+    #[inline]
+    pub fn reset(&mut self) {
+        let instant_now = Instant::now();
+        self.last_tick = instant_now;
+        self.last_frame = instant_now;
+        self.accumulator = Duration::ZERO;
+    }
+
+    #[inline]
+    pub fn next_tick(&self) -> Instant {
+        self.last_tick + self.period
+    }
+
+    #[inline]
+    pub fn fps(&self) -> f64 {
+        1.0 / self.period.as_secs_f64()
+    }
+
+    /// takes effect from the next frame.
+    ///
+    /// # Panics
+    ///
+    /// panics if `fps` is not positive and finite.
+    #[inline]
+    pub fn set_fps(&mut self, fps: f64) {
+        self.set_period(Self::fps_to_period(fps));
+    }
+
+    #[inline]
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// takes effect from the next frame.
+    ///
+    /// # Panics
+    ///
+    /// panics if `period` is zero.
+    #[inline]
+    pub fn set_period(&mut self, period: Duration) {
+        assert!(!period.is_zero(), "`period` must be non-zero");
+        self.period = period;
+    }
+
+    #[inline]
+    pub fn fixed_step(&self) -> Duration {
+        self.fixed_step
+    }
+
+    /// # Panics
+    ///
+    /// panics if `fixed_step` is zero.
+    #[inline]
+    pub fn set_fixed_step(&mut self, fixed_step: Duration) {
+        assert!(!fixed_step.is_zero(), "`fixed_step` must be non-zero");
+        self.fixed_step = fixed_step;
+    }
+
+    /// at most this many fixed updates in a frame, the rest of a long frame is discarded
+    /// instead of slowing down the following frames.
+    #[inline]
+    pub fn max_fixed_steps(&self) -> u32 {
+        self.max_fixed_steps
+    }
+
+    #[inline]
+    pub fn set_max_fixed_steps(&mut self, max_fixed_steps: u32) {
+        self.max_fixed_steps = max_fixed_steps;
+    }
+
+    /// number of frames completed.
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// number of frames dropped in total.
+    #[inline]
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+}
+
+impl FramePacer {
+    #[inline]
+    fn fps_to_period(fps: f64) -> Duration {
+        assert!(
+            fps.is_finite() && fps > 0.0,
+            "`fps` must be positive and finite"
+        );
+        Duration::from_secs_f64(1.0 / fps)
+    }
+
+    fn frame(&mut self, tick: Instant, instant_now: Instant) -> Frame {
+        // the ticks passed while waiting are dropped, the schedule stays aligned.
+        let dropped = (instant_now.saturating_duration_since(tick).as_nanos()
+            / self.period.as_nanos())
+        .try_into()
+        .unwrap_or(u64::MAX);
+        let tick = tick
+            + self
+                .period
+                .saturating_mul(dropped.try_into().unwrap_or(u32::MAX));
+        self.last_tick = tick;
+        self.frames += 1;
+        self.dropped_frames += dropped;
+
+        let delta = instant_now.saturating_duration_since(self.last_frame);
+        self.last_frame = instant_now;
+        self.accumulator += delta;
+        let steps = self.accumulator.as_nanos() / self.fixed_step.as_nanos();
+        let fixed_steps = steps.min(self.max_fixed_steps as u128) as u32;
+        self.accumulator = if steps > fixed_steps as u128 {
+            // discards the rest, keeps the phase of the fixed updates.
+            Duration::from_nanos((self.accumulator.as_nanos() % self.fixed_step.as_nanos()) as u64)
+        } else {
+            self.accumulator - self.fixed_step * fixed_steps
+        };
+        Frame {
+            tick,
+            delta,
+            fixed_steps,
+            alpha: self.accumulator.div_duration_f64(self.fixed_step),
+            dropped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let period = Duration::from_millis(5);
+        let mut pacer = FramePacer::with_period(period);
+        pacer.set_fixed_step(period / 2);
+        pacer.set_max_fixed_steps(u32::MAX);
+        let start = pacer.next_tick() - period;
+        let (mut fixed_steps, mut total_delta) = (0, Duration::ZERO);
+        for i in 1..=20 {
+            let frame = pacer.wait();
+            assert!(Instant::now() >= frame.tick);
+            assert!((0.0..1.0).contains(&frame.alpha));
+            fixed_steps += frame.fixed_steps;
+            total_delta += frame.delta;
+            assert_eq!(pacer.frames(), i as u64);
+            (pacer.dropped_frames() == 0).then(|| assert_eq!(frame.tick, start + period * i));
+        }
+        assert_eq!(pacer.frames(), 20);
+        // about 2 fixed steps per frame, nothing discarded.
+        assert_eq!(fixed_steps, total_delta.div_duration_f64(period / 2) as u32);
+
+        // stall for 3.5 frames.
+        std::thread::sleep(period * 7 / 2);
+        let dropped = pacer.dropped_frames();
+        let frame = pacer.wait();
+        // the ticks of 1 and 2 frames later are dropped, this one is late.
+        assert!(frame.dropped >= 2);
+        assert_eq!(pacer.dropped_frames(), dropped + frame.dropped);
+        assert!(frame.delta >= period * 7 / 2);
+        assert!(frame.fixed_steps >= 7);
+
+        pacer.set_fps(50.0);
+        assert_eq!(pacer.period(), Duration::from_millis(20));
+        let tick = pacer.next_tick();
+        let frame = pacer.wait();
+        assert!(frame.dropped > 0 || frame.tick == tick);
+        assert!(Instant::now() >= tick);
+    }
+
+    #[test]
+    fn max_fixed_steps() {
+        let period = Duration::from_millis(2);
+        let mut pacer = FramePacer::with_period(period);
+        pacer.set_max_fixed_steps(2);
+        std::thread::sleep(period * 10);
+        let frame = pacer.wait();
+        assert_eq!(frame.fixed_steps, 2);
+        assert!(frame.alpha < 1.0);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn async_tick() {
+        use crate::async_::FutureWait;
+
+        let period = Duration::from_millis(5);
+        let mut pacer = FramePacer::with_period(period);
+        let begin = Instant::now();
+        async {
+            for _ in 0..10 {
+                pacer.tick().await;
+            }
+        }
+        .wait();
+        assert!(begin.elapsed() >= period * 10);
+        assert_eq!(pacer.frames(), 10);
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops::Range;
+
+use super::ThreadPool;
+
+/// a source that can be split into chunks for the parallel helpers of [`ThreadPool`].
+pub trait ParSource: IntoIterator<Item: Send> + Send + Sized {
+    fn len(&self) -> usize;
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `index` is in `0..=len`.
+    fn split_at(self, index: usize) -> (Self, Self);
+}
+
+impl<T: Sync> ParSource for &[T] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        <[T]>::split_at(self, index)
+    }
+}
+
+impl<T: Send> ParSource for &mut [T] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        <[T]>::split_at_mut(self, index)
+    }
+}
+
+impl ParSource for Range<usize> {
+    #[inline]
+    fn len(&self) -> usize {
+        ExactSizeIterator::len(self)
+    }
+
+    #[inline]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let mid = self.start + index;
+        (self.start..mid, mid..self.end)
+    }
+}
+
+impl ThreadPool {
+    /// number of chunks per worker, more chunks balance the load better.
+    const CHUNKS_PER_WORKER: usize = 4;
+
+    /// calls `f` on each item in parallel.
+    ///
+    /// if `f` panics, the panic is resumed after all chunks are finished.
+    pub fn par_for_each<S: ParSource>(&self, source: S, f: impl Fn(S::Item) + Sync) {
+        self.par_chunks(source, |chunk| chunk.into_iter().for_each(&f));
+    }
+
+    /// calls `f` on each item in parallel, the outputs are in the order of the items.
+    ///
+    /// if `f` panics, the panic is resumed after all chunks are finished.
+    pub fn par_map<S: ParSource, R: Send>(
+        &self,
+        source: S,
+        f: impl Fn(S::Item) -> R + Sync,
+    ) -> Vec<R> {
+        let len = source.len();
+        self.par_chunks(source, |chunk| {
+            chunk.into_iter().map(&f).collect::<Vec<_>>()
+        })
+        .into_iter()
+        .fold(Vec::with_capacity(len), |mut r, chunk| {
+            r.extend(chunk);
+            r
+        })
+    }
+
+    /// maps each item by `map` and reduces them by `reduce` in parallel, returns `None` if
+    /// `source` is empty.
+    ///
+    /// `reduce` should be associative, the outputs are reduced in the order of the items.
+    ///
+    /// if `map` or `reduce` panics, the panic is resumed after all chunks are finished.
+    pub fn par_reduce<S: ParSource, R: Send>(
+        &self,
+        source: S,
+        map: impl Fn(S::Item) -> R + Sync,
+        reduce: impl Fn(R, R) -> R + Sync,
+    ) -> Option<R> {
+        self.par_chunks(source, |chunk| chunk.into_iter().map(&map).reduce(&reduce))
+            .into_iter()
+            .flatten()
+            .reduce(&reduce)
+    }
+
+    /// splits `source` by [`num_workers`](Self::num_workers) and calls `f` on each chunk in
+    /// parallel, returns the outputs in the order of the chunks.
+    fn par_chunks<S: ParSource, R: Send>(&self, source: S, f: impl Fn(S) -> R + Sync) -> Vec<R> {
+        let num_chunks = self.num_workers().max(1) * Self::CHUNKS_PER_WORKER;
+        let chunk_len = source.len().div_ceil(num_chunks).max(1);
+        let f = &f;
+        self.scope(|s| {
+            let mut handles = Vec::with_capacity(num_chunks);
+            let mut source = source;
+            while source.len() > chunk_len {
+                let (chunk, rest) = source.split_at(chunk_len);
+                handles.push(s.spawn(move || f(chunk)));
+                source = rest;
+            }
+            (!source.is_empty()).then(|| handles.push(s.spawn(move || f(source))));
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|p| std::panic::resume_unwind(p)))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::AssertUnwindSafe;
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let thread_pool = ThreadPool::new(4.try_into().expect("non-zero"));
+        let mut data: Vec<_> = (0..1000_u64).collect();
+
+        thread_pool.par_for_each(data.as_mut_slice(), |x| *x *= 2);
+        assert!(data.iter().enumerate().all(|(i, x)| *x == i as u64 * 2));
+
+        let squares = thread_pool.par_map(data.as_slice(), |x| x * x);
+        assert_eq!(squares, data.iter().map(|x| x * x).collect::<Vec<_>>());
+
+        let sum = thread_pool.par_reduce(0..1001, |i| i as u64, |a, b| a + b);
+        assert_eq!(sum, Some(1000 * 1001 / 2));
+        let concat = thread_pool.par_reduce(
+            0..26,
+            |i| ((b'a' + i as u8) as char).to_string(),
+            |a, b| a + &b,
+        );
+        assert_eq!(concat.as_deref(), Some("abcdefghijklmnopqrstuvwxyz"));
+
+        assert_eq!(thread_pool.par_reduce(0..0, |i| i, |a, b| a + b), None);
+        assert!(thread_pool.par_map(&[] as &[u8], |x| *x).is_empty());
+    }
+
+    #[test]
+    fn panic() {
+        let thread_pool = ThreadPool::new(2.try_into().expect("non-zero"));
+        let r = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            thread_pool.par_for_each(0..100, |i| assert_ne!(i, 42, "chunk panic"));
+        }));
+        assert!(r.is_err_and(|p| {
+            p.downcast_ref::<String>()
+                .is_some_and(|s| s.contains("chunk panic"))
+        }));
+        assert_eq!(thread_pool.par_map(0..3, |i| i), [0, 1, 2]);
+    }
+}
@@ -0,0 +1,207 @@
+use std::{marker::PhantomData, panic::AssertUnwindSafe, sync::Arc};
+
+use parking_lot::{Condvar, Mutex};
+
+use super::{PanicPayload, ThreadPool};
+
+struct ScopeData {
+    num_running: Mutex<usize>,
+    condvar: Condvar,
+    /// the first panic of the tasks that are not joined.
+    panic: Mutex<Option<PanicPayload>>,
+}
+
+impl ScopeData {
+    #[inline]
+    fn increment(&self) {
+        *self.num_running.lock() += 1;
+    }
+
+    #[inline]
+    fn decrement(&self) {
+        let mut num_running = self.num_running.lock();
+        *num_running -= 1;
+        (*num_running == 0).then(|| self.condvar.notify_all());
+    }
+
+    fn wait(&self) {
+        let mut num_running = self.num_running.lock();
+        self.condvar.wait_while(&mut num_running, |n| *n != 0);
+    }
+}
+
+/// result of a scoped task, shared by the task and its [`ScopedJoinHandle`].
+struct Packet<T> {
+    scope_data: Arc<ScopeData>,
+    result: Mutex<Option<std::thread::Result<T>>>,
+    condvar: Condvar,
+}
+
+impl<T> Drop for Packet<T> {
+    fn drop(&mut self) {
+        // the handle is dropped without taking the panic.
+        if let Some(Err(payload)) = self.result.get_mut().take() {
+            self.scope_data.panic.lock().get_or_insert(payload);
+        }
+    }
+}
+
+/// see [`ThreadPool::scope`].
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'env ThreadPool,
+    data: Arc<ScopeData>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// runs `f` on a worker of the pool, `f` can borrow anything outliving the scope.
+    pub fn spawn<T: Send + 'scope>(
+        &'scope self,
+        f: impl FnOnce() -> T + Send + 'scope,
+    ) -> ScopedJoinHandle<'scope, T> {
+        let packet = Arc::new(Packet {
+            scope_data: self.data.clone(),
+            result: Mutex::new(None),
+            condvar: Condvar::new(),
+        });
+        let task_packet = packet.clone();
+        let data = self.data.clone();
+        data.increment();
+        let task: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            let r = std::panic::catch_unwind(AssertUnwindSafe(f));
+            *task_packet.result.lock() = Some(r);
+            task_packet.condvar.notify_all();
+            // a panic not joined is recorded when the packet is dropped.
+            drop(task_packet);
+            data.decrement();
+        });
+        // Safety: `ThreadPool::scope` waits for all tasks before `'scope` ends.
+        let task = unsafe {
+            core::mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Box<dyn FnOnce() + Send>>(
+                task,
+            )
+        };
+        self.pool.add_task_boxed(task);
+        ScopedJoinHandle {
+            packet,
+            scope: PhantomData,
+        }
+    }
+}
+
+/// handle of a task spawned by [`Scope::spawn`].
+pub struct ScopedJoinHandle<'scope, T> {
+    packet: Arc<Packet<T>>,
+    scope: PhantomData<&'scope ()>,
+}
+
+impl<T> ScopedJoinHandle<'_, T> {
+    /// blocking until the task is finished, returns the panic if the task panics.
+    pub fn join(self) -> std::thread::Result<T> {
+        let mut result = self.packet.result.lock();
+        self.packet.condvar.wait_while(&mut result, |r| r.is_none());
+        unsafe { result.take().unwrap_unchecked() }
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.packet.result.lock().is_some()
+    }
+}
+
+impl ThreadPool {
+    /// like [`std::thread::scope`], but the tasks run on the workers of this pool.
+    ///
+    /// all tasks spawned in the scope are finished before this returns. if `f` or any task not
+    /// joined panics, the panic is resumed after that.
+    ///
+    /// don't call this in a task of the same pool, the workers may wait for each other forever.
+    pub fn scope<'env, T>(
+        &'env self,
+        f: impl for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+    ) -> T {
+        let scope = Scope {
+            pool: self,
+            data: Arc::new(ScopeData {
+                num_running: Mutex::new(0),
+                condvar: Condvar::new(),
+                panic: Mutex::new(None),
+            }),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        let r = std::panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        scope.data.wait();
+        let r = r.unwrap_or_else(|payload| std::panic::resume_unwind(payload));
+        if let Some(payload) = scope.data.panic.lock().take() {
+            std::panic::resume_unwind(payload);
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let thread_pool = ThreadPool::new(4.try_into().expect("non-zero"));
+        let mut data: Vec<_> = (0..1000_u64).collect();
+        let sum = thread_pool.scope(|s| {
+            let handles: Vec<_> = data
+                .chunks(100)
+                .map(|chunk| s.spawn(move || chunk.iter().sum::<u64>()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("scoped task panic"))
+                .sum::<u64>()
+        });
+        assert_eq!(sum, 999 * 1000 / 2);
+
+        thread_pool.scope(|s| {
+            data.chunks_mut(100).for_each(|chunk| {
+                s.spawn(move || {
+                    std::thread::sleep(Duration::from_millis(10));
+                    chunk.iter_mut().for_each(|x| *x *= 2);
+                });
+            });
+        });
+        assert!(data.iter().enumerate().all(|(i, x)| *x == i as u64 * 2));
+    }
+
+    #[test]
+    fn nested() {
+        let thread_pool = ThreadPool::new(2.try_into().expect("non-zero"));
+        let count = std::sync::atomic::AtomicUsize::new(0);
+        thread_pool.scope(|s| {
+            (0..4).for_each(|_| {
+                s.spawn(|| {
+                    s.spawn(|| count.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+                    count.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+                });
+            });
+        });
+        assert_eq!(count.into_inner(), 8);
+    }
+
+    #[test]
+    fn panic() {
+        let thread_pool = ThreadPool::new(2.try_into().expect("non-zero"));
+        let joined = thread_pool.scope(|s| s.spawn(|| panic!("joined")).join());
+        assert!(joined.is_err());
+
+        let r = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            thread_pool.scope(|s| {
+                s.spawn(|| panic!("not joined"));
+            })
+        }));
+        assert!(r.is_err_and(|p| p.downcast_ref::<&str>() == Some(&"not joined")));
+        // the workers survive.
+        assert_eq!(thread_pool.scope(|s| s.spawn(|| 1).join().ok()), Some(1));
+    }
+}
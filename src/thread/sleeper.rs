@@ -0,0 +1,174 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// statistics of [`PreciseSleeper`], see [`PreciseSleeper::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SleepStats {
+    /// number of sleeps.
+    pub sleeps: u64,
+    /// average time the os sleeps longer than requested.
+    pub mean_oversleep: Duration,
+    /// current spin window before the deadline.
+    pub spin_threshold: Duration,
+    /// average time woken up after the deadline.
+    pub mean_error: Duration,
+    pub max_error: Duration,
+}
+
+struct SleeperState {
+    /// ewma of the oversleep in nanoseconds.
+    oversleep: f64,
+    /// ewma of the absolute deviation of the oversleep in nanoseconds.
+    deviation: f64,
+    sleeps: u64,
+    total_error: Duration,
+    max_error: Duration,
+}
+
+/// sleeps with the os sleep first, then spins until the deadline.
+///
+/// the spin window adapts to the oversleep of the os sleep measured at runtime, so the
+/// sleeper spins as short as possible without missing the deadline.
+pub struct PreciseSleeper {
+    state: Mutex<SleeperState>,
+}
+
+impl Default for PreciseSleeper {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PreciseSleeper {
+    /// weight of a new sample in the ewma.
+    const ALPHA: f64 = 1.0 / 8.0;
+
+    /// the spin window is the mean oversleep plus this many deviations.
+    const DEVIATIONS: f64 = 4.0;
+
+    const MIN_SPIN_THRESHOLD: Duration = Duration::from_micros(50);
+    const MAX_SPIN_THRESHOLD: Duration = Duration::from_millis(20);
+
+    /// starts with a 1ms spin window.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_spin_threshold(Duration::from_millis(1))
+    }
+
+    /// `spin_threshold` is the initial spin window before any sleep is measured.
+    pub fn with_spin_threshold(spin_threshold: Duration) -> Self {
+        Self {
+            state: Mutex::new(SleeperState {
+                oversleep: spin_threshold.as_nanos() as f64 / 2.0,
+                deviation: spin_threshold.as_nanos() as f64 / (2.0 * Self::DEVIATIONS),
+                sleeps: 0,
+                total_error: Duration::ZERO,
+                max_error: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// the process-wide sleeper used by [`precise_sleep`](super::precise_sleep).
+    pub fn global() -> &'static Self {
+        static GLOBAL: LazyLock<PreciseSleeper> = LazyLock::new(PreciseSleeper::new);
+        &GLOBAL
+    }
+
+    #[inline]
+    pub fn sleep(&self, duration: Duration) {
+        self.sleep_until(Instant::now() + duration);
+    }
+
+    pub fn sleep_until(&self, deadline: Instant) {
+        let spin_threshold = self.spin_threshold();
+        let instant_now = Instant::now();
+        if let Some(duration) = deadline
+            .checked_duration_since(instant_now)
+            .and_then(|d| d.checked_sub(spin_threshold))
+            .filter(|d| !d.is_zero())
+        {
+            std::thread::sleep(duration);
+            let oversleep = instant_now.elapsed().saturating_sub(duration);
+            self.record_oversleep(oversleep);
+        }
+        super::spin_sleep_until(deadline);
+        self.record_error(Instant::now().saturating_duration_since(deadline));
+    }
+
+    pub fn spin_threshold(&self) -> Duration {
+        let state = self.state.lock();
+        let nanos = state.oversleep + Self::DEVIATIONS * state.deviation;
+        Duration::from_nanos(nanos as u64).clamp(Self::MIN_SPIN_THRESHOLD, Self::MAX_SPIN_THRESHOLD)
+    }
+
+    pub fn stats(&self) -> SleepStats {
+        let spin_threshold = self.spin_threshold();
+        let state = self.state.lock();
+        SleepStats {
+            sleeps: state.sleeps,
+            mean_oversleep: Duration::from_nanos(state.oversleep as u64),
+            spin_threshold,
+            mean_error: state
+                .total_error
+                .checked_div(state.sleeps.try_into().unwrap_or(u32::MAX))
+                .unwrap_or_default(),
+            max_error: state.max_error,
+        }
+    }
+
+    /// resets the counters of the errors, the calibration is kept.
+    pub fn reset_stats(&self) {
+        let mut state = self.state.lock();
+        state.sleeps = 0;
+        state.total_error = Duration::ZERO;
+        state.max_error = Duration::ZERO;
+    }
+}
+
+impl PreciseSleeper {
+    fn record_oversleep(&self, oversleep: Duration) {
+        let sample = oversleep.as_nanos() as f64;
+        let mut state = self.state.lock();
+        let deviation = (sample - state.oversleep).abs();
+        state.deviation += Self::ALPHA * (deviation - state.deviation);
+        state.oversleep += Self::ALPHA * (sample - state.oversleep);
+    }
+
+    fn record_error(&self, error: Duration) {
+        let mut state = self.state.lock();
+        state.sleeps += 1;
+        state.total_error += error;
+        state.max_error = state.max_error.max(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t1() {
+        let sleeper = PreciseSleeper::new();
+        let initial = sleeper.stats();
+        assert_eq!(initial.sleeps, 0);
+        assert!(initial.spin_threshold >= PreciseSleeper::MIN_SPIN_THRESHOLD);
+        for _ in 0..50 {
+            let begin = Instant::now();
+            sleeper.sleep(Duration::from_millis(5));
+            assert!(begin.elapsed() >= Duration::from_millis(5));
+        }
+        let stats = sleeper.stats();
+        assert_eq!(stats.sleeps, 50);
+        assert!(stats.mean_error <= stats.max_error);
+        assert!(stats.spin_threshold >= PreciseSleeper::MIN_SPIN_THRESHOLD);
+
+        sleeper.reset_stats();
+        assert_eq!(sleeper.stats().sleeps, 0);
+        assert_eq!(sleeper.stats().spin_threshold, stats.spin_threshold);
+    }
+}
@@ -1,17 +1,26 @@
-use std::{num::NonZero, thread::JoinHandle, time::Instant};
+use std::{
+    sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
 use crossbeam_channel::{Receiver as MpscReceiver, RecvTimeoutError, Sender as MpscSender};
 
-use crate::sync::{TimerPool, timer::TimerTaskFn};
+use crate::sync::{
+    TimerBackend, TimerHandle, TimerPool,
+    timer::{Inbox, RepeatingTaskFn, TimerTaskFn},
+};
 
-enum TimerTask {
-    Task(Box<TimerTaskFn>),
+enum Signal {
+    /// a timer is added or rescheduled.
+    Wake,
     Exit,
 }
 
 pub struct TimerThread {
     join_handle: Option<JoinHandle<()>>,
-    task_sender: MpscSender<(Instant, TimerTask)>,
+    inbox: Arc<Inbox>,
+    signal_sender: MpscSender<Signal>,
 }
 
 impl Default for TimerThread {
@@ -38,27 +47,90 @@ impl TimerThread {
         Self::with_builder_and_capacity(builder, 0)
     }
 
+    #[inline]
     pub fn with_builder_and_capacity(
         builder: std::thread::Builder,
         capacity: usize,
     ) -> std::io::Result<Self> {
-        let (task_sender, task_receiver) = crossbeam_channel::unbounded();
-        let join_handle =
-            builder.spawn(move || Self::thread_main(task_receiver, NonZero::new(capacity)))?;
+        Self::with_builder_and_pool(builder, TimerPool::with_capacity(capacity))
+    }
+
+    /// see [`TimerPool::with_backend`].
+    #[inline]
+    pub fn with_builder_and_backend(
+        builder: std::thread::Builder,
+        backend: TimerBackend,
+    ) -> std::io::Result<Self> {
+        Self::with_builder_and_pool(builder, TimerPool::with_backend(backend))
+    }
+
+    /// runs the timers of `timer_pool`, e.g. a pool with a [`ManualClock`](crate::sync::ManualClock).
+    ///
+    /// if the clock of the pool is manual, the timers not reached yet are cancelled on
+    /// [`join`](Self::join), the clock may never reach them.
+    pub fn with_builder_and_pool(
+        builder: std::thread::Builder,
+        timer_pool: TimerPool,
+    ) -> std::io::Result<Self> {
+        let (signal_sender, signal_receiver) = crossbeam_channel::unbounded();
+        let inbox = timer_pool.inbox().clone();
+        let sender = signal_sender.clone();
+        inbox.set_notify(move || {
+            let _ = sender.send(Signal::Wake);
+        });
+        let sender = signal_sender.clone();
+        timer_pool
+            .clock()
+            .on_advance(Box::new(move || sender.send(Signal::Wake).is_ok()));
+        let join_handle = builder.spawn(move || Self::thread_main(signal_receiver, timer_pool))?;
         Ok(Self {
             join_handle: Some(join_handle),
-            task_sender,
+            inbox,
+            signal_sender,
         })
     }
 
     #[inline]
-    pub fn add_task(&self, deadline: Instant, task: impl FnOnce(&mut TimerPool) + Send + 'static) {
-        self.add_task_boxed(deadline, Box::new(task));
+    pub fn add_task(
+        &self,
+        deadline: Instant,
+        task: impl FnOnce(&mut TimerPool) + Send + 'static,
+    ) -> TimerHandle {
+        self.add_task_boxed(deadline, Box::new(task))
+    }
+
+    #[inline]
+    pub fn add_task_boxed(&self, deadline: Instant, task: Box<TimerTaskFn>) -> TimerHandle {
+        self.inbox.add_task_boxed(deadline, task)
+    }
+
+    /// see [`TimerPool::add_repeating`].
+    ///
+    /// repeating timers are cancelled on [`join`](Self::join).
+    ///
+    /// # Panics
+    ///
+    /// panics if `period` is zero.
+    #[inline]
+    pub fn add_repeating(
+        &self,
+        start: Instant,
+        period: Duration,
+        task: impl FnMut(&mut TimerPool) + Send + 'static,
+    ) -> TimerHandle {
+        self.add_repeating_boxed(start, period, Box::new(task))
     }
 
+    /// see [`add_repeating`](Self::add_repeating).
     #[inline]
-    pub fn add_task_boxed(&self, deadline: Instant, task: Box<TimerTaskFn>) {
-        self.send((deadline, TimerTask::Task(task)));
+    pub fn add_repeating_boxed(
+        &self,
+        start: Instant,
+        period: Duration,
+        task: Box<RepeatingTaskFn>,
+    ) -> TimerHandle {
+        assert!(!period.is_zero(), "`period` must be non-zero");
+        self.inbox.add_repeating_boxed(start, period, task)
     }
 
     pub fn join(mut self) -> std::thread::Result<()> {
@@ -68,58 +140,60 @@ impl TimerThread {
 
 impl TimerThread {
     #[inline]
-    fn thread_main(
-        task_receiver: MpscReceiver<(Instant, TimerTask)>,
-        capacity: Option<NonZero<usize>>,
-    ) {
-        let mut timer_pool = if let Some(capacity) = capacity {
-            TimerPool::with_capacity(capacity.into())
-        } else {
-            TimerPool::new()
-        };
+    fn thread_main(signal_receiver: MpscReceiver<Signal>, mut timer_pool: TimerPool) {
         let mut need_exit = false;
+        let is_manual = timer_pool.clock().is_manual();
 
         loop {
             // step 1: poll timers.
             while let Some(task) = timer_pool.poll() {
                 task(&mut timer_pool);
             }
+            if need_exit && is_manual {
+                timer_pool.clear();
+            } else if need_exit {
+                // the tasks may add repeating timers.
+                timer_pool.cancel_repeating();
+            }
 
-            // step 2: if has deadline, block until deadline, else until a new task arrived.
-            let new_task = if let Some(deadline) = timer_pool.peek() {
-                match task_receiver.recv_deadline(*deadline) {
-                    Ok(task) => Some(task),
+            // step 2: if has deadline, block until deadline, else until a new timer arrived.
+            let signal = if is_manual && !timer_pool.is_empty() {
+                // woken up by the clock.
+                Some(signal_receiver.recv().expect("unreachable"))
+            } else if let Some(deadline) = timer_pool.peek() {
+                match signal_receiver.recv_deadline(*deadline) {
+                    Ok(signal) => Some(signal),
                     Err(RecvTimeoutError::Timeout) => None,
                     _ => unreachable!(),
                 }
-            } else if need_exit {
-                // no deadline, then `time_pool` is empty, exit now.
+            } else if need_exit && timer_pool.is_empty() {
+                // no deadline and no timer in the inbox, exit now.
                 return;
             } else {
-                Some(task_receiver.recv().expect("unreachable"))
+                Some(signal_receiver.recv().expect("unreachable"))
             };
 
-            // `Self::join` take `self`, so no `TimerTask::Task` after `TimerTask::Exit`.
-            let mut f = |task| match task {
-                (deadline, TimerTask::Task(task)) => timer_pool.add_task_boxed(deadline, task),
-                (_, TimerTask::Exit) => need_exit = true,
+            // `Self::join` take `self`, so no new timer after `Signal::Exit`.
+            let mut f = |signal| match signal {
+                Signal::Wake => (),
+                Signal::Exit => need_exit = true,
             };
 
-            new_task.map(&mut f);
+            signal.map(&mut f);
 
-            // step 3: receive remaining tasks.
-            task_receiver.try_iter().for_each(&mut f);
+            // step 3: receive remaining signals.
+            signal_receiver.try_iter().for_each(&mut f);
         }
     }
 
     #[inline]
-    fn send(&self, timer: (Instant, TimerTask)) {
-        self.task_sender.send(timer).expect("unreachable");
+    fn send(&self, signal: Signal) {
+        self.signal_sender.send(signal).expect("unreachable");
     }
 
     fn join_by_ref(&mut self) -> Option<std::thread::Result<()>> {
         self.join_handle.take().map(|j| {
-            self.send((Instant::now(), TimerTask::Exit));
+            self.send(Signal::Exit);
             j.join()
         })
     }
@@ -154,4 +228,66 @@ mod tests {
         let elapsed = instant_now.elapsed();
         println!("elapsed: {:#?}", elapsed);
     }
+
+    #[test]
+    fn handle() {
+        let timer_thread = TimerThread::new();
+        let instant_now = Instant::now();
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        let s = sender.clone();
+        let cancelled = timer_thread.add_task(instant_now + Duration::from_millis(50), move |_| {
+            s.send("cancelled").expect("unreachable")
+        });
+        let s = sender.clone();
+        let later = timer_thread.add_task(instant_now + Duration::from_secs(10), move |_| {
+            s.send("rescheduled").expect("unreachable")
+        });
+        assert!(cancelled.cancel());
+        assert!(later.reschedule(instant_now + Duration::from_millis(100)));
+
+        let ticks = timer_thread.add_repeating(instant_now, Duration::from_millis(20), move |_| {
+            sender.send("tick").expect("unreachable")
+        });
+        assert_eq!(receiver.recv().ok(), Some("tick"));
+        assert_eq!(receiver.recv().ok(), Some("tick"));
+        assert!(ticks.cancel());
+        let rest: Vec<_> = receiver.iter().take_while(|s| *s == "tick").collect();
+        assert!(rest.len() <= 1);
+        assert!(instant_now.elapsed() < Duration::from_secs(5));
+        assert!(timer_thread.join().is_ok());
+    }
+
+    #[test]
+    fn manual_clock() {
+        let clock = Arc::new(crate::sync::ManualClock::new());
+        let timer_pool = TimerPool::with_clock(clock.clone());
+        let timer_thread =
+            TimerThread::with_builder_and_pool(std::thread::Builder::new(), timer_pool)
+                .expect("failed to create thread");
+        let start = clock.now();
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        let s = sender.clone();
+        timer_thread.add_task(start + Duration::from_secs(10), move |_| {
+            s.send("fired").expect("unreachable")
+        });
+        let ticks = timer_thread.add_repeating(start, Duration::from_secs(3), move |_| {
+            sender.send("tick").expect("unreachable")
+        });
+        assert_eq!(receiver.recv().ok(), Some("tick"));
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+
+        clock.advance(Duration::from_secs(10));
+        // the repeating timer catches up first, 3s, 6s, 9s.
+        let fired: Vec<_> = (0..4).filter_map(|_| receiver.recv().ok()).collect();
+        assert_eq!(fired, ["tick", "tick", "tick", "fired"]);
+
+        // never reached by the clock, cancelled on join.
+        let pending = timer_thread.add_task(start + Duration::from_secs(3600), |_| ());
+        assert!(timer_thread.join().is_ok());
+        assert!(!pending.is_active());
+        assert!(!ticks.is_active());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
 }
@@ -1,48 +1,462 @@
-use std::{num::NonZero, thread::JoinHandle};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    num::NonZero,
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc,
+        atomic::{self, AtomicUsize},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
-use crossbeam_channel::{Receiver as MpmcReceiver, Sender as MpmcSender};
+use crossbeam_channel::{
+    Receiver as MpmcReceiver, RecvTimeoutError, SendTimeoutError, Sender as MpmcSender,
+    TrySendError,
+};
+use parking_lot::{Condvar, Mutex};
 
-use crate::sync::spsc::{self, OnceReceiver};
+use crate::sync::spsc::{self, OnceReceiver, OnceSender};
+
+use super::PanicPayload;
+
+type BoxedTask = Box<dyn FnOnce() + Send>;
 
 enum Task {
-    Task(Box<dyn FnOnce() + Send>),
+    /// a task is pushed to [`Shared::tasks`], the worker pops the most urgent one.
+    Ticket,
     Exit,
 }
 
-struct RawWorkerThread(JoinHandle<()>);
+/// priority of a task in [`ThreadPool`].
+///
+/// a task waits at most [`AGING`](Self::AGING) per level for the tasks of higher priority added
+/// after it, so low priority tasks still make progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub const AGING: Duration = Duration::from_millis(100);
+
+    /// tasks with earlier deadlines run first.
+    #[inline]
+    fn deadline(self, now: Instant) -> Instant {
+        now + Self::AGING * (Self::High as u32 - self as u32)
+    }
+}
+
+struct PriorityTask {
+    deadline: Instant,
+    /// tasks with the same deadline run in order.
+    seq: u64,
+    task: BoxedTask,
+}
+
+impl PartialEq for PriorityTask {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PriorityTask {}
+
+impl PartialOrd for PriorityTask {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PriorityTask {
+    /// reversed, so the earliest one is the greatest in [`BinaryHeap`].
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.deadline, other.seq).cmp(&(self.deadline, self.seq))
+    }
+}
+
+#[derive(Default)]
+struct TaskHeap {
+    heap: BinaryHeap<PriorityTask>,
+    next_seq: u64,
+}
+
+type BuilderFn = Box<dyn FnMut(usize) -> std::thread::Builder + Send>;
+
+#[derive(thiserror::Error)]
+pub enum TryAddTaskError {
+    #[error("task queue is full")]
+    Full(Box<dyn FnOnce() + Send>),
+}
+
+impl std::fmt::Debug for TryAddTaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full(_) => f.debug_tuple("Full").finish_non_exhaustive(),
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum AddTaskTimeoutError {
+    #[error("timed out waiting for space in task queue")]
+    Timeout(Box<dyn FnOnce() + Send>),
+}
+
+impl std::fmt::Debug for AddTaskTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timeout(_) => f.debug_tuple("Timeout").finish_non_exhaustive(),
+        }
+    }
+}
 
-impl RawWorkerThread {
+/// `None` means unbounded.
+#[inline]
+fn task_channel(queue_capacity: Option<usize>) -> (MpmcSender<Task>, MpmcReceiver<Task>) {
+    queue_capacity.map_or_else(crossbeam_channel::unbounded, crossbeam_channel::bounded)
+}
+
+/// the ticket is sent first, so the task waits for space in the task queue.
+#[inline]
+fn send(task_sender: &MpmcSender<Task>, shared: &Arc<Shared>, priority: Priority, task: BoxedTask) {
+    task_sender.send(Task::Ticket).expect("unreachable");
+    shared.push_task(priority, task);
+    shared.grow();
+}
+
+#[inline]
+fn try_send(
+    task_sender: &MpmcSender<Task>,
+    shared: &Arc<Shared>,
+    priority: Priority,
+    task: BoxedTask,
+) -> Result<(), TryAddTaskError> {
+    match task_sender.try_send(Task::Ticket) {
+        Ok(()) => {
+            shared.push_task(priority, task);
+            shared.grow();
+            Ok(())
+        }
+        Err(TrySendError::Full(_)) => Err(TryAddTaskError::Full(task)),
+        Err(TrySendError::Disconnected(_)) => unreachable!(),
+    }
+}
+
+#[inline]
+fn send_timeout(
+    task_sender: &MpmcSender<Task>,
+    shared: &Arc<Shared>,
+    priority: Priority,
+    task: BoxedTask,
+    timeout: Duration,
+) -> Result<(), AddTaskTimeoutError> {
+    match task_sender.send_timeout(Task::Ticket, timeout) {
+        Ok(()) => {
+            shared.push_task(priority, task);
+            shared.grow();
+            Ok(())
+        }
+        Err(SendTimeoutError::Timeout(_)) => Err(AddTaskTimeoutError::Timeout(task)),
+        Err(SendTimeoutError::Disconnected(_)) => unreachable!(),
+    }
+}
+
+/// what a worker does after a task panics.
+///
+/// the panic message is printed by the panic hook in any case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PanicPolicy {
+    /// the worker keeps running.
+    #[default]
+    Continue,
+    /// the worker thread exits, a new thread takes its place.
+    ///
+    /// if the new thread fails to spawn, the old one keeps running, and the panic is returned by
+    /// `join` as under [`Propagate`](Self::Propagate).
+    Respawn,
+    /// the worker keeps running, the first panic is returned by `join`.
+    Propagate,
+}
+
+/// configuration of an elastic [`ThreadPool`].
+///
+/// a worker is added when the waiting tasks outnumber the idle workers, a worker idle for
+/// `keep_alive` retires.
+///
+/// if a worker fails to spawn, the pool keeps running with the others, and `join` returns the
+/// [`std::io::Error`] as the panic payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Elastic {
+    pub min_workers: usize,
+    pub max_workers: NonZero<usize>,
+    pub keep_alive: Duration,
+}
+
+/// options of a [`WorkerThread`] or a [`ThreadPool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ThreadPoolConfig {
+    /// `add_task` blocks while the task queue is full, `None` means unbounded.
+    pub queue_capacity: Option<usize>,
+    pub panic_policy: PanicPolicy,
+    /// ignored by [`WorkerThread`].
+    pub elastic: Option<Elastic>,
+}
+
+#[derive(Default)]
+struct WorkerCount {
+    live: usize,
+    /// index of the next worker, passed to the builder.
+    next_index: usize,
+    /// set by `join`, the workers no longer grow or retire.
+    shutdown: bool,
+}
+
+/// spawns `num_workers` workers built by `builder`, `respawn_builder` builds the workers added
+/// later.
+fn spawn_shared(
+    num_workers: usize,
+    config: ThreadPoolConfig,
+    respawn_builder: Option<BuilderFn>,
+    mut builder: impl FnMut(&Shared, usize) -> std::thread::Builder,
+) -> std::io::Result<(Arc<Shared>, MpmcSender<Task>)> {
+    let (task_sender, task_receiver) = task_channel(config.queue_capacity);
+    let shared = Shared::new(
+        task_receiver,
+        config.panic_policy,
+        respawn_builder,
+        config.elastic,
+    );
+    shared.spawn_workers(num_workers, |index| builder(&shared, index))?;
+    Ok((shared, task_sender))
+}
+
+/// shared by all workers of a [`WorkerThread`] or a [`ThreadPool`].
+struct Shared {
+    task_receiver: MpmcReceiver<Task>,
+    /// a task is pushed right after its [`Task::Ticket`] is sent.
+    tasks: Mutex<TaskHeap>,
+    tasks_condvar: Condvar,
+    panic_policy: PanicPolicy,
+    /// builds the thread of a respawned or an elastic worker.
+    builder: Option<Mutex<BuilderFn>>,
+    elastic: Option<Elastic>,
+    workers: Mutex<WorkerCount>,
+    num_idle: AtomicUsize,
+    /// the first panic under [`PanicPolicy::Propagate`], or a panic not respawned, or the
+    /// [`std::io::Error`] of a worker failed to spawn by [`grow`](Self::grow).
+    panic: Mutex<Option<PanicPayload>>,
+    /// threads of all workers, including the respawned ones.
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Shared {
     fn new(
         task_receiver: MpmcReceiver<Task>,
-        builder: std::thread::Builder,
-    ) -> std::io::Result<Self> {
-        let join_handle = builder.spawn(|| Self::thread_main(task_receiver))?;
-        Ok(Self(join_handle))
+        panic_policy: PanicPolicy,
+        builder: Option<BuilderFn>,
+        elastic: Option<Elastic>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            task_receiver,
+            tasks: Mutex::new(TaskHeap::default()),
+            tasks_condvar: Condvar::new(),
+            panic_policy,
+            builder: builder.map(Mutex::new),
+            elastic,
+            workers: Mutex::new(WorkerCount::default()),
+            num_idle: AtomicUsize::new(0),
+            panic: Mutex::new(None),
+            threads: Mutex::new(Vec::new()),
+        })
     }
 
-    /// before [`join`](Self::join), ensure that this worker can receive a [`Task::Exit`].
+    fn spawn_workers(
+        self: &Arc<Self>,
+        num_workers: usize,
+        mut builder: impl FnMut(usize) -> std::thread::Builder,
+    ) -> std::io::Result<()> {
+        (0..num_workers).try_for_each(|index| self.spawn(index, builder(index)))?;
+        *self.workers.lock() = WorkerCount {
+            live: num_workers,
+            next_index: num_workers,
+            shutdown: false,
+        };
+        Ok(())
+    }
+
+    fn spawn(self: &Arc<Self>, index: usize, builder: std::thread::Builder) -> std::io::Result<()> {
+        let shared = self.clone();
+        let join_handle = builder.spawn(move || shared.thread_main(index))?;
+        let mut threads = self.threads.lock();
+        // retired workers of an elastic pool.
+        threads.retain(|t| !t.is_finished());
+        threads.push(join_handle);
+        Ok(())
+    }
+
+    /// adds a worker if the pool is elastic and the waiting tasks outnumber the idle workers.
+    fn grow(self: &Arc<Self>) {
+        let Some(elastic) = self.elastic else {
+            return;
+        };
+        let index = {
+            // checked while locked, so a worker retiring meanwhile is not counted as idle, see
+            // [`retire`](Self::retire).
+            let mut workers = self.workers.lock();
+            if self.task_receiver.len() <= self.num_idle.load(atomic::Ordering::Acquire) {
+                return;
+            }
+            if workers.shutdown || workers.live >= elastic.max_workers.get() {
+                return;
+            }
+            workers.live += 1;
+            workers.next_index += 1;
+            workers.next_index - 1
+        };
+        if let Err(err) = self.spawn(index, self.build(index)) {
+            self.workers.lock().live -= 1;
+            self.panic.lock().get_or_insert(Box::new(err));
+        }
+    }
+
+    /// returns `true` if the idle worker should exit.
+    ///
+    /// a task sent after the worker timed out may be left to it by [`grow`](Self::grow), so the
+    /// worker never retires while a task is waiting.
+    fn retire(&self) -> bool {
+        let min_workers = self.elastic.map_or(usize::MAX, |e| e.min_workers);
+        let mut workers = self.workers.lock();
+        let retire =
+            !workers.shutdown && workers.live > min_workers && self.task_receiver.is_empty();
+        retire.then(|| workers.live -= 1);
+        retire
+    }
+
+    #[inline]
+    fn num_workers(&self) -> usize {
+        self.workers.lock().live
+    }
+
+    /// returns the number of workers to receive a [`Task::Exit`].
+    fn shutdown(&self) -> usize {
+        let mut workers = self.workers.lock();
+        workers.shutdown = true;
+        workers.live
+    }
+
+    fn recv(&self) -> Result<Task, RecvTimeoutError> {
+        self.num_idle.fetch_add(1, atomic::Ordering::AcqRel);
+        let task = match self.elastic {
+            Some(elastic) => self.task_receiver.recv_timeout(elastic.keep_alive),
+            None => self
+                .task_receiver
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        self.num_idle.fetch_sub(1, atomic::Ordering::AcqRel);
+        task
+    }
+
+    fn push_task(&self, priority: Priority, task: BoxedTask) {
+        let mut tasks = self.tasks.lock();
+        let seq = tasks.next_seq;
+        tasks.next_seq += 1;
+        tasks.heap.push(PriorityTask {
+            deadline: priority.deadline(Instant::now()),
+            seq,
+            task,
+        });
+        drop(tasks);
+        self.tasks_condvar.notify_one();
+    }
+
+    /// called after a [`Task::Ticket`] is received.
+    fn pop_task(&self) -> BoxedTask {
+        let mut tasks = self.tasks.lock();
+        // the task may not be pushed yet.
+        self.tasks_condvar
+            .wait_while(&mut tasks, |tasks| tasks.heap.is_empty());
+        unsafe { tasks.heap.pop().unwrap_unchecked() }.task
+    }
+
+    /// only called if [`builder`](Self::builder) is provided.
+    #[inline]
+    fn build(&self, index: usize) -> std::thread::Builder {
+        unsafe { self.builder.as_ref().unwrap_unchecked() }.lock()(index)
+    }
+
+    /// returns `true` if the new thread is spawned.
+    fn respawn(self: &Arc<Self>, index: usize) -> bool {
+        self.spawn(index, self.build(index)).is_ok()
+    }
+
+    /// before [`join`](Self::join), ensure that each worker can receive a [`Task::Exit`].
     ///
     /// othewise `join` will block forever...
-    fn join(self) -> std::thread::Result<()> {
-        self.0.join()
+    fn join(&self) -> std::thread::Result<()> {
+        let mut result = Ok(());
+        loop {
+            // a respawned thread is pushed before the old one exits.
+            let Some(thread) = self.threads.lock().pop() else {
+                break;
+            };
+            let r = thread.join();
+            result = result.and(r);
+        }
+        let panic = self.panic.lock().take();
+        result.and(panic.map_or(Ok(()), Err))
     }
 }
 
-impl RawWorkerThread {
+impl Shared {
     #[inline]
-    fn thread_main(task_receiver: MpmcReceiver<Task>) {
+    fn thread_main(self: Arc<Self>, index: usize) {
         loop {
-            let task = task_receiver.recv().expect("unreachable");
-            match task {
-                Task::Task(task) => task(),
-                Task::Exit => return,
+            match self.recv() {
+                Ok(Task::Ticket) => (),
+                Ok(Task::Exit) | Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) if self.retire() => return,
+                Err(RecvTimeoutError::Timeout) => continue,
+            }
+            let task = self.pop_task();
+            let Err(payload) = std::panic::catch_unwind(AssertUnwindSafe(task)) else {
+                continue;
+            };
+            match self.panic_policy {
+                PanicPolicy::Continue => (),
+                PanicPolicy::Respawn => {
+                    if self.respawn(index) {
+                        return;
+                    }
+                    self.panic.lock().get_or_insert(payload);
+                }
+                PanicPolicy::Propagate => {
+                    self.panic.lock().get_or_insert(payload);
+                }
             }
         }
     }
 }
 
+/// runs `task` under [`catch_unwind`](std::panic::catch_unwind), so a panic is sent to
+/// `r_sender` instead of going through the [`PanicPolicy`].
+fn sync_task<R: Send + 'static>(
+    task: impl FnOnce() -> R + Send + 'static,
+    r_sender: OnceSender<Result<R, PanicPayload>>,
+) -> BoxedTask {
+    Box::new(|| r_sender.send(std::panic::catch_unwind(AssertUnwindSafe(task))))
+}
+
 pub struct WorkerThread {
-    raw: Option<RawWorkerThread>,
+    shared: Option<Arc<Shared>>,
     task_sender: MpmcSender<Task>,
 }
 
@@ -60,9 +474,41 @@ impl WorkerThread {
     }
 
     pub fn with_builder(builder: std::thread::Builder) -> std::io::Result<Self> {
-        let (task_sender, task_receiver) = crossbeam_channel::unbounded();
-        let raw = Some(RawWorkerThread::new(task_receiver, builder)?);
-        Ok(Self { raw, task_sender })
+        Self::spawn(builder, None, ThreadPoolConfig::default())
+    }
+
+    #[inline]
+    pub fn with_config(config: ThreadPoolConfig) -> Self {
+        Self::with_builder_and_config(std::thread::Builder::new, config)
+            .expect("failed to spawn thread")
+    }
+
+    /// `builder` is also called to respawn the worker under [`PanicPolicy::Respawn`].
+    pub fn with_builder_and_config(
+        mut builder: impl FnMut() -> std::thread::Builder + Send + 'static,
+        config: ThreadPoolConfig,
+    ) -> std::io::Result<Self> {
+        let first = builder();
+        Self::spawn(first, Some(Box::new(move |_| builder())), config)
+    }
+
+    fn spawn(
+        builder: std::thread::Builder,
+        respawn_builder: Option<BuilderFn>,
+        config: ThreadPoolConfig,
+    ) -> std::io::Result<Self> {
+        let config = ThreadPoolConfig {
+            elastic: None,
+            ..config
+        };
+        let mut builder = Some(builder);
+        let (shared, task_sender) = spawn_shared(1, config, respawn_builder, |_, _| unsafe {
+            builder.take().unwrap_unchecked()
+        })?;
+        Ok(Self {
+            shared: Some(shared),
+            task_sender,
+        })
     }
 
     #[inline]
@@ -70,21 +516,70 @@ impl WorkerThread {
         self.add_task_boxed(Box::new(task));
     }
 
+    /// a panic of `task` is returned by the receiver.
     pub fn add_task_sync<R: Send + 'static>(
         &self,
         task: impl FnOnce() -> R + Send + 'static,
-    ) -> OnceReceiver<R> {
+    ) -> OnceReceiver<Result<R, PanicPayload>> {
         let (r_sender, r_receiver) = spsc::once();
-        let task = Box::new(|| r_sender.send(task()));
-        self.add_task_boxed(task);
+        self.add_task_boxed(sync_task(task, r_sender));
         r_receiver
     }
 
     #[inline]
     pub fn add_task_boxed(&self, task: Box<dyn FnOnce() + Send>) {
-        self.send(Task::Task(task));
+        send(&self.task_sender, self.shared(), Priority::Normal, task);
     }
 
+    /// fails if the task queue is full.
+    #[inline]
+    pub fn try_add_task(
+        &self,
+        task: impl FnOnce() + Send + 'static,
+    ) -> Result<(), TryAddTaskError> {
+        try_send(
+            &self.task_sender,
+            self.shared(),
+            Priority::Normal,
+            Box::new(task),
+        )
+    }
+
+    /// waits at most `timeout` for space in the task queue.
+    #[inline]
+    pub fn add_task_timeout(
+        &self,
+        task: impl FnOnce() + Send + 'static,
+        timeout: Duration,
+    ) -> Result<(), AddTaskTimeoutError> {
+        send_timeout(
+            &self.task_sender,
+            self.shared(),
+            Priority::Normal,
+            Box::new(task),
+            timeout,
+        )
+    }
+
+    /// number of tasks waiting in the task queue.
+    #[inline]
+    pub fn queue_len(&self) -> usize {
+        self.task_sender.len()
+    }
+
+    /// `None` means unbounded.
+    #[inline]
+    pub fn queue_capacity(&self) -> Option<usize> {
+        self.task_sender.capacity()
+    }
+
+    #[inline]
+    pub fn panic_policy(&self) -> PanicPolicy {
+        self.shared().panic_policy
+    }
+
+    /// returns the first panic of the tasks under [`PanicPolicy::Propagate`], or a panic not
+    /// respawned under [`PanicPolicy::Respawn`].
     #[inline]
     pub fn join(mut self) -> std::thread::Result<()> {
         unsafe { self.join_by_ref().unwrap_unchecked() }
@@ -93,26 +588,32 @@ impl WorkerThread {
 
 impl WorkerThread {
     #[inline]
-    fn send(&self, task: Task) {
-        self.task_sender.send(task).expect("unreachable");
+    fn shared(&self) -> &Arc<Shared> {
+        unsafe { self.shared.as_ref().unwrap_unchecked() }
+    }
+
+    #[inline]
+    fn send_exit(&self) {
+        self.task_sender.send(Task::Exit).expect("unreachable");
     }
 
     fn join_by_ref(&mut self) -> Option<std::thread::Result<()>> {
-        self.raw.take().map(|j| {
-            self.send(Task::Exit);
-            j.join()
+        self.shared.take().map(|shared| {
+            self.send_exit();
+            shared.join()
         })
     }
 }
 
 impl Drop for WorkerThread {
     fn drop(&mut self) {
-        self.join_by_ref().map(|r| r.expect("WorkerThread panic"));
+        self.join_by_ref()
+            .map(|r| (!std::thread::panicking()).then(|| r.expect("WorkerThread panic")));
     }
 }
 
 pub struct ThreadPool {
-    workers: Option<Box<[RawWorkerThread]>>,
+    shared: Option<Arc<Shared>>,
     task_sender: MpmcSender<Task>,
 }
 
@@ -127,14 +628,53 @@ impl ThreadPool {
         num_workers: NonZero<usize>,
         mut builder: impl FnMut(usize) -> std::thread::Builder,
     ) -> std::io::Result<Self> {
-        let num_workers = num_workers.get();
-        let (task_sender, task_receiver) = crossbeam_channel::unbounded();
-        let mut workers = Vec::with_capacity(num_workers);
-        for index in 0..num_workers {
-            workers.push(RawWorkerThread::new(task_receiver.clone(), builder(index))?);
-        }
+        let (shared, task_sender) = spawn_shared(
+            num_workers.get(),
+            ThreadPoolConfig::default(),
+            None,
+            |_, index| builder(index),
+        )?;
         Ok(Self {
-            workers: Some(workers.into_boxed_slice()),
+            shared: Some(shared),
+            task_sender,
+        })
+    }
+
+    #[inline]
+    pub fn with_config(num_workers: NonZero<usize>, config: ThreadPoolConfig) -> Self {
+        Self::with_builder_and_config(num_workers, |_| std::thread::Builder::new(), config)
+            .expect("failed to create thread")
+    }
+
+    /// `builder` is also called to respawn a worker under [`PanicPolicy::Respawn`], and to add a
+    /// worker to an elastic pool.
+    ///
+    /// if the pool is elastic, `num_workers` is clamped to `min_workers..=max_workers`.
+    ///
+    /// # Panics
+    ///
+    /// panics if `min_workers` is greater than `max_workers`.
+    pub fn with_builder_and_config(
+        num_workers: NonZero<usize>,
+        builder: impl FnMut(usize) -> std::thread::Builder + Send + 'static,
+        config: ThreadPoolConfig,
+    ) -> std::io::Result<Self> {
+        let num_workers = match config.elastic {
+            Some(elastic) => {
+                assert!(
+                    elastic.min_workers <= elastic.max_workers.get(),
+                    "min_workers > max_workers"
+                );
+                num_workers
+                    .get()
+                    .clamp(elastic.min_workers, elastic.max_workers.get())
+            }
+            None => num_workers.get(),
+        };
+        let (shared, task_sender) =
+            spawn_shared(num_workers, config, Some(Box::new(builder)), Shared::build)?;
+        Ok(Self {
+            shared: Some(shared),
             task_sender,
         })
     }
@@ -144,26 +684,97 @@ impl ThreadPool {
         self.add_task_boxed(Box::new(task));
     }
 
+    /// a panic of `task` is returned by the receiver.
     pub fn add_task_sync<R: Send + 'static>(
         &self,
         task: impl FnOnce() -> R + Send + 'static,
-    ) -> OnceReceiver<R> {
+    ) -> OnceReceiver<Result<R, PanicPayload>> {
         let (r_sender, r_receiver) = spsc::once();
-        let task = Box::new(|| r_sender.send(task()));
-        self.add_task_boxed(task);
+        self.add_task_boxed(sync_task(task, r_sender));
         r_receiver
     }
 
     #[inline]
     pub fn add_task_boxed(&self, task: Box<dyn FnOnce() + Send>) {
-        self.send(Task::Task(task));
+        send(&self.task_sender, self.shared(), Priority::Normal, task);
     }
 
     #[inline]
+    pub fn add_task_with_priority(&self, priority: Priority, task: impl FnOnce() + Send + 'static) {
+        send(&self.task_sender, self.shared(), priority, Box::new(task));
+    }
+
+    /// a panic of `task` is returned by the receiver.
+    pub fn add_task_sync_with_priority<R: Send + 'static>(
+        &self,
+        priority: Priority,
+        task: impl FnOnce() -> R + Send + 'static,
+    ) -> OnceReceiver<Result<R, PanicPayload>> {
+        let (r_sender, r_receiver) = spsc::once();
+        send(
+            &self.task_sender,
+            self.shared(),
+            priority,
+            sync_task(task, r_sender),
+        );
+        r_receiver
+    }
+
+    /// fails if the task queue is full.
+    #[inline]
+    pub fn try_add_task(
+        &self,
+        task: impl FnOnce() + Send + 'static,
+    ) -> Result<(), TryAddTaskError> {
+        try_send(
+            &self.task_sender,
+            self.shared(),
+            Priority::Normal,
+            Box::new(task),
+        )
+    }
+
+    /// waits at most `timeout` for space in the task queue.
+    #[inline]
+    pub fn add_task_timeout(
+        &self,
+        task: impl FnOnce() + Send + 'static,
+        timeout: Duration,
+    ) -> Result<(), AddTaskTimeoutError> {
+        send_timeout(
+            &self.task_sender,
+            self.shared(),
+            Priority::Normal,
+            Box::new(task),
+            timeout,
+        )
+    }
+
+    /// number of tasks waiting in the task queue.
+    #[inline]
+    pub fn queue_len(&self) -> usize {
+        self.task_sender.len()
+    }
+
+    /// `None` means unbounded.
+    #[inline]
+    pub fn queue_capacity(&self) -> Option<usize> {
+        self.task_sender.capacity()
+    }
+
+    #[inline]
+    /// the current number of workers, which changes over time if the pool is elastic.
     pub fn num_workers(&self) -> usize {
-        unsafe { self.workers.as_ref().unwrap_unchecked() }.len()
+        self.shared().num_workers()
     }
 
+    #[inline]
+    pub fn panic_policy(&self) -> PanicPolicy {
+        self.shared().panic_policy
+    }
+
+    /// returns the first panic of the tasks under [`PanicPolicy::Propagate`], or a panic not
+    /// respawned under [`PanicPolicy::Respawn`].
     #[inline]
     pub fn join(mut self) -> std::thread::Result<()> {
         unsafe { self.join_by_ref().unwrap_unchecked() }
@@ -172,21 +783,27 @@ impl ThreadPool {
 
 impl ThreadPool {
     #[inline]
-    fn send(&self, task: Task) {
-        self.task_sender.send(task).expect("unreachable");
+    fn shared(&self) -> &Arc<Shared> {
+        unsafe { self.shared.as_ref().unwrap_unchecked() }
+    }
+
+    #[inline]
+    fn send_exit(&self) {
+        self.task_sender.send(Task::Exit).expect("unreachable");
     }
 
     fn join_by_ref(&mut self) -> Option<std::thread::Result<()>> {
-        self.workers.take().map(|workers| {
-            workers.iter().for_each(|_| self.send(Task::Exit));
-            workers.into_iter().try_for_each(|w| w.join())
+        self.shared.take().map(|shared| {
+            (0..shared.shutdown()).for_each(|_| self.send_exit());
+            shared.join()
         })
     }
 }
 
 impl Drop for ThreadPool {
     fn drop(&mut self) {
-        self.join_by_ref().map(|r| r.expect("ThreadPool panic"));
+        self.join_by_ref()
+            .map(|r| (!std::thread::panicking()).then(|| r.expect("ThreadPool panic")));
     }
 }
 
@@ -217,4 +834,266 @@ mod tests {
         });
         println!("hello!");
     }
+
+    #[test]
+    fn panic_policy() {
+        let num_workers = 2.try_into().expect("non-zero");
+        let thread_pool = ThreadPool::new(num_workers);
+        thread_pool.add_task(|| panic!("continue"));
+        let r = thread_pool.add_task_sync(|| panic!("sync")).recv();
+        assert!(r.is_err_and(|p| p.downcast_ref::<&str>() == Some(&"sync")));
+        assert_eq!(thread_pool.add_task_sync(|| 1).recv().ok(), Some(1));
+        assert!(thread_pool.join().is_ok());
+
+        let worker = WorkerThread::with_builder_and_config(
+            || std::thread::Builder::new().name("respawn".to_owned()),
+            ThreadPoolConfig {
+                panic_policy: PanicPolicy::Respawn,
+                ..Default::default()
+            },
+        )
+        .expect("failed to spawn thread");
+        let thread_id = || std::thread::current().id();
+        let old_id = worker.add_task_sync(thread_id).recv().ok();
+        worker.add_task(|| panic!("respawn"));
+        let new_id = worker.add_task_sync(thread_id).recv().ok();
+        assert!(new_id.is_some() && new_id != old_id);
+        let name = worker.add_task_sync(|| std::thread::current().name().map(str::to_owned));
+        assert_eq!(name.recv().ok().flatten().as_deref(), Some("respawn"));
+        assert!(worker.join().is_ok());
+
+        // the new thread fails to spawn, so the panic is propagated.
+        let mut first = true;
+        let builder = move || {
+            let builder = std::thread::Builder::new();
+            match core::mem::take(&mut first) {
+                true => builder,
+                false => builder.stack_size(usize::MAX / 2),
+            }
+        };
+        let config = ThreadPoolConfig {
+            panic_policy: PanicPolicy::Respawn,
+            ..Default::default()
+        };
+        let worker =
+            WorkerThread::with_builder_and_config(builder, config).expect("failed to spawn thread");
+        worker.add_task(|| panic!("not respawned"));
+        assert_eq!(worker.add_task_sync(|| 1).recv().ok(), Some(1));
+        let r = worker.join();
+        assert!(r.is_err_and(|p| p.downcast_ref::<&str>() == Some(&"not respawned")));
+
+        let worker = WorkerThread::with_config(ThreadPoolConfig {
+            panic_policy: PanicPolicy::Propagate,
+            ..Default::default()
+        });
+        worker.add_task(|| panic!("propagate"));
+        worker.add_task(|| panic!("ignored"));
+        assert_eq!(worker.add_task_sync(|| 1).recv().ok(), Some(1));
+        let r = worker.join();
+        assert!(r.is_err_and(|p| p.downcast_ref::<&str>() == Some(&"propagate")));
+    }
+
+    #[test]
+    fn queue_capacity() {
+        let worker = WorkerThread::with_config(ThreadPoolConfig {
+            queue_capacity: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(worker.queue_capacity(), Some(2));
+        let (gate_sender, gate_receiver) = spsc::once::<()>();
+        let (started_sender, started_receiver) = spsc::once::<()>();
+        worker.add_task(move || {
+            started_sender.send(());
+            gate_receiver.recv();
+        });
+        started_receiver.recv();
+        let ran = Arc::new(AtomicUsize::new(0));
+        (0..2).for_each(|_| {
+            let ran = ran.clone();
+            worker.add_task(move || _ = ran.fetch_add(1, atomic::Ordering::Relaxed));
+        });
+        assert_eq!(worker.queue_len(), 2);
+        assert!(matches!(
+            worker.try_add_task(|| ()),
+            Err(TryAddTaskError::Full(_))
+        ));
+        let r = worker.add_task_timeout(|| (), Duration::from_millis(10));
+        assert!(matches!(r, Err(AddTaskTimeoutError::Timeout(_))));
+
+        gate_sender.send(());
+        assert!(
+            worker
+                .add_task_timeout(|| (), Duration::from_secs(1))
+                .is_ok()
+        );
+        assert!(worker.join().is_ok());
+        assert_eq!(ran.load(atomic::Ordering::Relaxed), 2);
+        assert!(WorkerThread::new().queue_capacity().is_none());
+    }
+
+    #[test]
+    fn priority() {
+        let thread_pool = ThreadPool::new(1.try_into().expect("non-zero"));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let block = |thread_pool: &ThreadPool| {
+            let (gate_sender, gate_receiver) = spsc::once::<()>();
+            let (started_sender, started_receiver) = spsc::once::<()>();
+            thread_pool.add_task_with_priority(Priority::High, move || {
+                started_sender.send(());
+                gate_receiver.recv();
+            });
+            started_receiver.recv();
+            gate_sender
+        };
+        let push = |priority| {
+            let order = order.clone();
+            move || order.lock().push(priority)
+        };
+
+        let gate = block(&thread_pool);
+        [Priority::Low, Priority::Normal, Priority::High]
+            .into_iter()
+            .for_each(|p| thread_pool.add_task_with_priority(p, push(p)));
+        gate.send(());
+        let r = thread_pool.add_task_sync_with_priority(Priority::Low, || ());
+        assert!(r.recv().is_ok());
+        assert_eq!(
+            *order.lock(),
+            [Priority::High, Priority::Normal, Priority::Low]
+        );
+
+        // aging, the low priority task is older than `2 * AGING`.
+        order.lock().clear();
+        let gate = block(&thread_pool);
+        thread_pool.add_task_with_priority(Priority::Low, push(Priority::Low));
+        std::thread::sleep(Priority::AGING * 3);
+        thread_pool.add_task_with_priority(Priority::High, push(Priority::High));
+        gate.send(());
+        assert!(thread_pool.join().is_ok());
+        assert_eq!(*order.lock(), [Priority::Low, Priority::High]);
+    }
+
+    #[test]
+    fn elastic() {
+        let thread_pool = ThreadPool::with_builder_and_config(
+            1.try_into().expect("non-zero"),
+            |index| std::thread::Builder::new().name(format!("elastic {index}")),
+            ThreadPoolConfig {
+                elastic: Some(Elastic {
+                    min_workers: 1,
+                    max_workers: 4.try_into().expect("non-zero"),
+                    keep_alive: Duration::from_millis(100),
+                }),
+                ..Default::default()
+            },
+        )
+        .expect("failed to create thread");
+        assert_eq!(thread_pool.num_workers(), 1);
+
+        let names = Arc::new(Mutex::new(std::collections::HashSet::new()));
+        let receivers: Vec<_> = (0..4)
+            .map(|_| {
+                let names = names.clone();
+                thread_pool.add_task_sync(move || {
+                    let name = std::thread::current().name().map(str::to_owned);
+                    names.lock().insert(name);
+                    std::thread::sleep(Duration::from_millis(100));
+                })
+            })
+            .collect();
+        assert!(receivers.into_iter().all(|r| r.recv().is_ok()));
+        assert_eq!(names.lock().len(), 4);
+        assert!(
+            names
+                .lock()
+                .iter()
+                .all(|n| n.as_ref().is_some_and(|n| n.starts_with("elastic")))
+        );
+        assert_eq!(thread_pool.num_workers(), 4);
+
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(thread_pool.num_workers(), 1);
+        assert_eq!(thread_pool.add_task_sync(|| 1).recv().ok(), Some(1));
+        assert!(thread_pool.join().is_ok());
+
+        // the added workers fail to spawn.
+        let thread_pool = ThreadPool::with_builder_and_config(
+            1.try_into().expect("non-zero"),
+            |index| match index {
+                0 => std::thread::Builder::new(),
+                _ => std::thread::Builder::new().stack_size(usize::MAX / 2),
+            },
+            ThreadPoolConfig {
+                elastic: Some(Elastic {
+                    min_workers: 1,
+                    max_workers: 2.try_into().expect("non-zero"),
+                    keep_alive: Duration::from_millis(100),
+                }),
+                ..Default::default()
+            },
+        )
+        .expect("failed to create thread");
+        let receivers: Vec<_> = (0..4)
+            .map(|i| thread_pool.add_task_sync(move || i))
+            .collect();
+        let r: Vec<_> = receivers.into_iter().map(|r| r.recv().ok()).collect();
+        assert_eq!(r, (0..4).map(Some).collect::<Vec<_>>());
+        assert_eq!(thread_pool.num_workers(), 1);
+        let r = thread_pool.join();
+        assert!(r.is_err_and(|p| p.is::<std::io::Error>()));
+    }
+
+    #[test]
+    fn config() {
+        let thread_pool = ThreadPool::with_config(
+            8.try_into().expect("non-zero"),
+            ThreadPoolConfig {
+                queue_capacity: Some(4),
+                panic_policy: PanicPolicy::Respawn,
+                elastic: Some(Elastic {
+                    min_workers: 1,
+                    max_workers: 2.try_into().expect("non-zero"),
+                    keep_alive: Duration::from_secs(1),
+                }),
+            },
+        );
+        assert_eq!(thread_pool.num_workers(), 2);
+        assert_eq!(thread_pool.queue_capacity(), Some(4));
+        assert_eq!(thread_pool.panic_policy(), PanicPolicy::Respawn);
+        thread_pool.add_task(|| panic!("respawn"));
+        assert_eq!(thread_pool.add_task_sync(|| 1).recv().ok(), Some(1));
+        assert_eq!(thread_pool.num_workers(), 2);
+        assert!(thread_pool.join().is_ok());
+    }
+
+    #[test]
+    fn elastic_retire() {
+        // workers retire and grow all the time, no task is left without a worker.
+        let thread_pool = ThreadPool::with_config(
+            1.try_into().expect("non-zero"),
+            ThreadPoolConfig {
+                elastic: Some(Elastic {
+                    min_workers: 0,
+                    max_workers: 2.try_into().expect("non-zero"),
+                    keep_alive: Duration::from_millis(1),
+                }),
+                ..Default::default()
+            },
+        );
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..500 {
+                        // around the keep-alive of the idle workers.
+                        std::thread::sleep(Duration::from_micros(800 + i % 40 * 10));
+                        let r = thread_pool
+                            .add_task_sync(move || i)
+                            .try_recv_timeout(Duration::from_secs(5));
+                        assert_eq!(r.ok().and_then(Result::ok), Some(i));
+                    }
+                });
+            }
+        });
+        assert!(thread_pool.join().is_ok());
+    }
 }